@@ -1,13 +1,130 @@
 use std::{
     collections::HashMap,
-    net::{SocketAddr, UdpSocket},
+    net::SocketAddr,
     time::{Duration, Instant},
 };
 
+use mio::net::UdpSocket;
 use quiche::h3::{self, NameValue};
 
+const SOCKET_TOKEN: mio::Token = mio::Token(0);
+
 const MAX_DATAGRAM_SIZE: usize = 1350;
 
+// Token prefix used to recognize our own Retry tokens (distinguishes them
+// from an empty token on a fresh Initial).
+const TOKEN_PREFIX: &[u8] = b"strobe";
+
+// How long a minted Retry token remains acceptable before we treat it as
+// stale and force the client to go through another Retry round-trip.
+const TOKEN_TTL_SECS: u64 = 10;
+
+fn unix_timestamp() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Process-lifetime MAC key, generated once on first use. Not persisted
+/// across restarts, which is fine: its only job is to make Retry tokens
+/// unforgeable for the lifetime of this server process, not across reboots.
+///
+/// `std::collections::hash_map::RandomState` is keyed from the OS's own CSPRNG
+/// on construction (the same mechanism `HashMap` relies on for its
+/// HashDoS resistance), so it's a real source of unpredictable material — but
+/// only the *construction* draws fresh entropy. Calling `RandomState::new()`
+/// twice does not give two independent keys (the second reuses the same
+/// per-thread seed, just advanced by a counter), so we build it once and
+/// derive k0/k1 by hashing distinct domain-separated strings through it.
+fn server_secret() -> (u64, u64) {
+    use std::collections::hash_map::RandomState;
+    use std::hash::{BuildHasher, Hasher};
+
+    static SECRET: std::sync::OnceLock<(u64, u64)> = std::sync::OnceLock::new();
+    *SECRET.get_or_init(|| {
+        let build = RandomState::new();
+        let mut h0 = build.build_hasher();
+        h0.write(b"strobe-retry-k0");
+        let mut h1 = build.build_hasher();
+        h1.write(b"strobe-retry-k1");
+        (h0.finish(), h1.finish())
+    })
+}
+
+/// SipHash-2-4, hand-rolled rather than pulling in a crate for one keyed
+/// hash (same rationale as the hand-rolled CRC32/FNV-1a elsewhere in this
+/// codebase). Unlike those two, this one is keyed: without `k0`/`k1` an
+/// attacker cannot predict the tag, which is what makes it usable as a MAC
+/// for Retry tokens.
+fn siphash24(k0: u64, k1: u64, data: &[u8]) -> u64 {
+    let mut v0 = 0x736f_6d65_7073_6575u64 ^ k0;
+    let mut v1 = 0x646f_7261_6e64_6f6du64 ^ k1;
+    let mut v2 = 0x6c79_6765_6e65_7261u64 ^ k0;
+    let mut v3 = 0x7465_6462_7974_6573u64 ^ k1;
+
+    macro_rules! round {
+        () => {{
+            v0 = v0.wrapping_add(v1);
+            v1 = v1.rotate_left(13);
+            v1 ^= v0;
+            v0 = v0.rotate_left(32);
+            v2 = v2.wrapping_add(v3);
+            v3 = v3.rotate_left(16);
+            v3 ^= v2;
+            v0 = v0.wrapping_add(v3);
+            v3 = v3.rotate_left(21);
+            v3 ^= v0;
+            v2 = v2.wrapping_add(v1);
+            v1 = v1.rotate_left(17);
+            v1 ^= v2;
+            v2 = v2.rotate_left(32);
+        }};
+    }
+
+    let len = data.len();
+    let end = len - (len % 8);
+    for chunk in data[..end].chunks_exact(8) {
+        let m = u64::from_le_bytes(chunk.try_into().unwrap());
+        v3 ^= m;
+        round!();
+        round!();
+        v0 ^= m;
+    }
+
+    let mut last_block = [0u8; 8];
+    last_block[..len - end].copy_from_slice(&data[end..]);
+    last_block[7] = len as u8;
+    let m = u64::from_le_bytes(last_block);
+    v3 ^= m;
+    round!();
+    round!();
+    v0 ^= m;
+
+    v2 ^= 0xff;
+    round!();
+    round!();
+    round!();
+    round!();
+
+    v0 ^ v1 ^ v2 ^ v3
+}
+
+/// Bytes the Retry token MAC is computed over: the client address binds the
+/// token to whoever receives the genuine Retry, the timestamp lets
+/// `validate_token` enforce an expiry window, and the DCID binds the token
+/// to the specific connection attempt it was minted for.
+fn mac_input(src: SocketAddr, dcid: &[u8], timestamp: u64) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(16 + 8 + dcid.len());
+    match src.ip() {
+        std::net::IpAddr::V4(a) => buf.extend_from_slice(&a.octets()),
+        std::net::IpAddr::V6(a) => buf.extend_from_slice(&a.octets()),
+    }
+    buf.extend_from_slice(&timestamp.to_le_bytes());
+    buf.extend_from_slice(dcid);
+    buf
+}
+
 // Simple static random CID generator
 fn random_cid() -> quiche::ConnectionId<'static> {
     let mut id = [0u8; 16];
@@ -18,6 +135,99 @@ fn random_cid() -> quiche::ConnectionId<'static> {
     quiche::ConnectionId::from_vec(id.to_vec())
 }
 
+/// Mint a stateless Retry token binding the client address, a mint
+/// timestamp and the original DCID under a server-secret keyed MAC, so a
+/// later Initial carrying this token can be validated without any
+/// server-side session state, and cannot be forged by an attacker who
+/// merely spoofs the victim's source address (they'd also need the secret
+/// to produce a tag `validate_token` accepts).
+fn mint_token(dcid: &quiche::ConnectionId, src: SocketAddr) -> Vec<u8> {
+    let timestamp = unix_timestamp();
+    let (k0, k1) = server_secret();
+    let tag = siphash24(k0, k1, &mac_input(src, dcid, timestamp));
+
+    let mut token = Vec::with_capacity(TOKEN_PREFIX.len() + 8 + 8 + dcid.len());
+    token.extend_from_slice(TOKEN_PREFIX);
+    token.extend_from_slice(&timestamp.to_le_bytes());
+    token.extend_from_slice(&tag.to_le_bytes());
+    token.extend_from_slice(dcid);
+    token
+}
+
+/// Validate a token minted by `mint_token`, returning the original DCID
+/// (the odcid to pass to `quiche::accept`) if the MAC matches for `src` and
+/// the token hasn't aged out of `TOKEN_TTL_SECS`.
+fn validate_token<'a>(src: SocketAddr, token: &'a [u8]) -> Option<quiche::ConnectionId<'a>> {
+    let prefix_len = TOKEN_PREFIX.len();
+    if token.len() < prefix_len + 16 || &token[..prefix_len] != TOKEN_PREFIX {
+        return None;
+    }
+
+    let timestamp = u64::from_le_bytes(token[prefix_len..prefix_len + 8].try_into().ok()?);
+    let tag = u64::from_le_bytes(token[prefix_len + 8..prefix_len + 16].try_into().ok()?);
+    let dcid = &token[prefix_len + 16..];
+
+    let now = unix_timestamp();
+    if now.saturating_sub(timestamp) > TOKEN_TTL_SECS {
+        return None;
+    }
+
+    let (k0, k1) = server_secret();
+    let expected = siphash24(k0, k1, &mac_input(src, dcid, timestamp));
+    if expected != tag {
+        return None;
+    }
+
+    Some(quiche::ConnectionId::from_ref(dcid))
+}
+
+/// Encode a batch of incremental top-k score updates as a single QUIC
+/// DATAGRAM payload: `[u16 count][count * (u32 id, f32 score)]`. DATAGRAMs
+/// are already self-delimiting at the transport layer, so the count prefix
+/// only exists to let one frame batch several updates together.
+fn encode_score_batch(updates: &[(u32, f32)]) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(2 + updates.len() * 8);
+    buf.extend_from_slice(&(updates.len() as u16).to_le_bytes());
+    for &(id, score) in updates {
+        buf.extend_from_slice(&id.to_le_bytes());
+        buf.extend_from_slice(&score.to_le_bytes());
+    }
+    buf
+}
+
+/// Tracks how much of a response body has been flushed onto a stream, so a
+/// large or slow-to-produce body can be handed to `send_body` in pieces
+/// across several loop iterations instead of in one shot.
+struct PendingBody {
+    buf: Vec<u8>,
+    offset: usize,
+}
+
+/// Push as much of `pending` onto `stream_id` as flow control allows right
+/// now. Returns `true` once the whole body (including `fin`) has been sent.
+/// On `Error::Done` (stream blocked) the offset is left where it is so the
+/// next call — after the connection has had a chance to send more data and
+/// the peer has had a chance to open window — picks up where this left off.
+fn try_flush_body(
+    h3c: &mut h3::Connection,
+    conn: &mut quiche::Connection,
+    stream_id: u64,
+    pending: &mut PendingBody,
+) -> bool {
+    while pending.offset < pending.buf.len() {
+        match h3c.send_body(conn, stream_id, &pending.buf[pending.offset..], true) {
+            Ok(0) => break,
+            Ok(written) => pending.offset += written,
+            Err(h3::Error::Done) => break,
+            Err(e) => {
+                eprintln!("send_body err: {e:?}");
+                break;
+            }
+        }
+    }
+    pending.offset >= pending.buf.len()
+}
+
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     let listen = std::env::args()
         .nth(1)
@@ -29,10 +239,14 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         .nth(3)
         .unwrap_or_else(|| "key.pem".to_string());
 
-    let socket = UdpSocket::bind(&listen)?;
-    socket.set_nonblocking(true)?;
+    let mut socket = UdpSocket::bind(listen.parse()?)?;
     eprintln!("listening on {listen}");
 
+    let mut poll = mio::Poll::new()?;
+    let mut events = mio::Events::with_capacity(1024);
+    poll.registry()
+        .register(&mut socket, SOCKET_TOKEN, mio::Interest::READABLE)?;
+
     let mut cfg = quiche::Config::new(quiche::PROTOCOL_VERSION)?;
     cfg.set_application_protos(quiche::h3::APPLICATION_PROTOCOL)?;
     cfg.set_max_idle_timeout(10_000);
@@ -42,13 +256,21 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     cfg.set_initial_max_streams_bidi(1_000_000);
     cfg.set_initial_max_streams_uni(1_000_000);
     cfg.enable_early_data();
-    cfg.set_disable_active_migration(true);
+    cfg.enable_dgram(true, 1024, 1024);
     cfg.load_cert_chain_from_pem_file(&cert)?;
     cfg.load_priv_key_from_pem_file(&key)?;
 
     let h3_cfg = quiche::h3::Config::new()?;
 
-    let mut conns: HashMap<SocketAddr, (quiche::Connection, Option<h3::Connection>)> = HashMap::new();
+    // Connections are keyed by their primary (original) SCID rather than the
+    // client's SocketAddr, so NAT rebinding / connection migration doesn't
+    // collide or orphan a connection. `cid_map` resolves any CID the
+    // connection has issued (via `new_scid`) back to that primary key.
+    let mut conns: HashMap<
+        quiche::ConnectionId<'static>,
+        (quiche::Connection, Option<h3::Connection>, HashMap<u64, PendingBody>),
+    > = HashMap::new();
+    let mut cid_map: HashMap<quiche::ConnectionId<'static>, quiche::ConnectionId<'static>> = HashMap::new();
 
     let mut in_buf = [0u8; 64 * 1024];
     let mut out_buf = [0u8; 64 * 1024];
@@ -58,22 +280,87 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let mut total_reqs: u64 = 0;
 
     loop {
+        // Block until the socket is readable or the nearest connection
+        // timer fires — no more spinning when the engine is idle.
+        let timeout = conns
+            .values()
+            .filter_map(|(conn, _, _)| conn.timeout())
+            .min();
+        if let Err(e) = poll.poll(&mut events, timeout) {
+            if e.kind() != std::io::ErrorKind::Interrupted {
+                return Err(Box::new(e));
+            }
+        }
+        let socket_readable = events.iter().any(|ev| ev.token() == SOCKET_TOKEN);
+
         // === Receive all available packets ===
         loop {
+            if !socket_readable {
+                break;
+            }
             match socket.recv_from(&mut in_buf) {
                 Ok((read, from)) => {
-                    let entry = conns.entry(from).or_insert_with(|| {
-                        let scid = random_cid();
-                        let local = socket.local_addr().unwrap();
-                        let conn = quiche::accept(&scid, None, local, from, &mut cfg).unwrap();
-                        (conn, None)
-                    });
-
-                    let recv_info = quiche::RecvInfo {
-                        from,
-                        to: socket.local_addr().unwrap(),
+                    let local = socket.local_addr().unwrap();
+
+                    let hdr = match quiche::Header::from_slice(&mut in_buf[..read], quiche::MAX_CONN_ID_LEN) {
+                        Ok(h) => h,
+                        Err(e) => {
+                            eprintln!("header parse error: {e:?}");
+                            continue;
+                        }
                     };
 
+                    let primary = cid_map.get(&hdr.dcid).cloned();
+
+                    let primary = match primary {
+                        Some(p) => p,
+                        None => {
+                            // Unknown DCID: this must be an Initial carving out a
+                            // new connection.
+                            if hdr.ty != quiche::Type::Initial {
+                                continue;
+                            }
+
+                            if hdr.token.as_deref().unwrap_or(&[]).is_empty() {
+                                let new_scid = random_cid();
+                                let token = mint_token(&hdr.dcid, from);
+                                match quiche::retry(
+                                    &hdr.scid,
+                                    &hdr.dcid,
+                                    &new_scid,
+                                    &token,
+                                    hdr.version,
+                                    &mut out_buf,
+                                ) {
+                                    Ok(written) => {
+                                        let _ = socket.send_to(&out_buf[..written], from);
+                                    }
+                                    Err(e) => eprintln!("retry build error: {e:?}"),
+                                }
+                                continue;
+                            }
+
+                            let token = hdr.token.as_deref().unwrap_or(&[]);
+                            let odcid = match validate_token(from, token) {
+                                Some(id) => id,
+                                None => {
+                                    eprintln!("invalid retry token from {from}");
+                                    continue;
+                                }
+                            };
+
+                            let scid = hdr.dcid.clone().into_owned();
+                            let conn = quiche::accept(&scid, Some(&odcid), local, from, &mut cfg).unwrap();
+                            conns.insert(scid.clone(), (conn, None, HashMap::new()));
+                            cid_map.insert(scid.clone(), scid.clone());
+                            scid
+                        }
+                    };
+
+                    let entry = conns.get_mut(&primary).unwrap();
+
+                    let recv_info = quiche::RecvInfo { from, to: local };
+
                     if let Err(e) = entry.0.recv(&mut in_buf[..read], recv_info) {
                         if e != quiche::Error::Done {
                             eprintln!("recv error: {e:?}");
@@ -88,8 +375,35 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         // === Process each connection ===
         let mut to_remove = Vec::new();
 
-        for (&peer_addr, (conn, h3_opt)) in conns.iter_mut() {
-            conn.on_timeout();
+        for (primary, (conn, h3_opt, pending_bodies)) in conns.iter_mut() {
+            // Only run the timeout machinery on connections whose deadline
+            // has actually elapsed, rather than every connection every pass.
+            if conn.timeout() == Some(Duration::ZERO) {
+                conn.on_timeout();
+            }
+
+            // Pick up any additional SCIDs the connection issued to its peer
+            // (used once `active_conn_id_limit` > 1) and retire stale ones so
+            // a migrated client's new CID still resolves to this entry.
+            while let Some(retired) = conn.retired_scid_next() {
+                cid_map.remove(&retired);
+            }
+            while conn.scids_left() > 0 {
+                let new_scid = random_cid();
+                let reset_token = u128::from_be_bytes(
+                    std::array::from_fn(|i| new_scid.as_ref().get(i).copied().unwrap_or(0)),
+                );
+                if conn.new_scid(&new_scid, reset_token, false).is_err() {
+                    break;
+                }
+                cid_map.insert(new_scid, primary.clone());
+            }
+
+            // Drain any DATAGRAMs the client sent us (not used server-side
+            // today, but must be read or they pile up in the recv queue).
+            while let Ok(len) = conn.dgram_recv(&mut in_buf) {
+                let _ = len;
+            }
 
             // Establish H3 layer
             if h3_opt.is_none() && conn.is_established() {
@@ -114,7 +428,39 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                                     h3::Header::new(b"content-type", b"text/plain"),
                                 ];
                                 let _ = h3c.send_response(conn, stream_id, &resp, false);
-                                let _ = h3c.send_body(conn, stream_id, b"hello\n", true);
+                                let mut pending = PendingBody { buf: b"hello\n".to_vec(), offset: 0 };
+                                if !try_flush_body(h3c, conn, stream_id, &mut pending) {
+                                    pending_bodies.insert(stream_id, pending);
+                                }
+                            } else if path.as_deref() == Some(b"/scores") {
+                                total_reqs += 1;
+                                // Push a progressively refined top-k over the
+                                // unreliable DATAGRAM channel, out-of-band from
+                                // the reliable H3 body below.
+                                let rounds: [&[(u32, f32)]; 2] = [
+                                    &[(7, 0.41), (3, 0.30)],
+                                    &[(7, 0.93), (3, 0.30), (12, 0.55)],
+                                ];
+                                for updates in rounds {
+                                    let frame = encode_score_batch(updates);
+                                    if let Err(e) = conn.dgram_send(&frame) {
+                                        if e != quiche::Error::Done {
+                                            eprintln!("dgram_send err: {e:?}");
+                                        }
+                                        break;
+                                    }
+                                }
+
+                                let resp = vec![
+                                    h3::Header::new(b":status", b"200"),
+                                    h3::Header::new(b"server", b"quiche"),
+                                    h3::Header::new(b"content-type", b"text/plain"),
+                                ];
+                                let _ = h3c.send_response(conn, stream_id, &resp, false);
+                                let mut pending = PendingBody { buf: b"final\n".to_vec(), offset: 0 };
+                                if !try_flush_body(h3c, conn, stream_id, &mut pending) {
+                                    pending_bodies.insert(stream_id, pending);
+                                }
                             } else {
                                 let resp = vec![
                                     h3::Header::new(b":status", b"404"),
@@ -135,6 +481,15 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 }
             }
 
+            // Resume any response bodies that were blocked on flow control,
+            // each from its own stashed offset so concurrent requests don't
+            // step on each other.
+            if let Some(h3c) = h3_opt.as_mut() {
+                pending_bodies.retain(|&stream_id, pending| {
+                    !try_flush_body(h3c, conn, stream_id, pending)
+                });
+            }
+
             // Flush pending QUIC packets
             loop {
                 match conn.send(&mut out_buf) {
@@ -151,12 +506,13 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             }
 
             if conn.is_closed() {
-                to_remove.push(peer_addr);
+                to_remove.push(primary.clone());
             }
         }
 
-        for addr in to_remove {
-            conns.remove(&addr);
+        for primary in to_remove {
+            conns.remove(&primary);
+            cid_map.retain(|_, p| *p != primary);
         }
 
         // Periodic logging (once per second)
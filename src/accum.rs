@@ -1,3 +1,6 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
 pub struct Accum {
     cap: usize,
     mask: usize,
@@ -6,6 +9,32 @@ pub struct Accum {
     tag: Vec<u32>,
     epoch: u32,
     touched: Vec<u32>,
+    // threshold from the most recent `finalize_topk` call (the min-heap
+    // root: the k-th best score, or the lowest score kept when fewer than k
+    // ids were touched); 0.0 until a finalize has run.
+    topk_threshold: f32,
+}
+
+// Min-heap element for `finalize_topk`: ordered by score, with ascending id
+// as a stable tie-break so equal-score entries sort deterministically.
+#[derive(Clone, Copy, PartialEq)]
+struct ScoredId {
+    score: f32,
+    id: u32,
+}
+impl Eq for ScoredId {}
+impl PartialOrd for ScoredId {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl Ord for ScoredId {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.score
+            .partial_cmp(&other.score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| self.id.cmp(&other.id))
+    }
 }
 impl Accum {
     pub fn with_capacity_pow2(pow2_capacity: usize) -> Self {
@@ -19,6 +48,7 @@ impl Accum {
             tag: vec![0u32; cap],
             epoch: 1,
             touched: Vec::with_capacity(8192),
+            topk_threshold: 0.0,
         }
     }
     #[inline(always)]
@@ -29,6 +59,7 @@ impl Accum {
             self.epoch = 1;
         }
         self.touched.clear();
+        self.topk_threshold = 0.0;
     }
     #[inline(always)]
     pub fn inc(&mut self, id: u32) -> bool {
@@ -88,6 +119,47 @@ impl Accum {
     pub fn iter_touched<'a>(&'a self) -> impl Iterator<Item = u32> + 'a {
         self.touched.iter().copied()
     }
+
+    /// Select the top-`k` touched ids by score in a single O(n log k) pass,
+    /// instead of forcing callers to sort every touched id themselves.
+    /// `k == 0` returns empty; `k >= touched.len()` degrades to a full sort.
+    /// Also refreshes `kth_threshold()` to the new heap root (the k-th best
+    /// score, or the lowest score when fewer than k ids were touched).
+    pub fn finalize_topk(&mut self, k: usize) -> Vec<(u32, f32)> {
+        if k == 0 || self.touched.is_empty() {
+            self.topk_threshold = 0.0;
+            return Vec::new();
+        }
+        let mut heap: BinaryHeap<Reverse<ScoredId>> = BinaryHeap::with_capacity(k.min(self.touched.len()));
+        for &id in &self.touched {
+            let score = self.get_score(id);
+            if heap.len() < k {
+                heap.push(Reverse(ScoredId { score, id }));
+            } else if score > heap.peek().unwrap().0.score {
+                heap.pop();
+                heap.push(Reverse(ScoredId { score, id }));
+            }
+        }
+        self.topk_threshold = heap.peek().map(|r| r.0.score).unwrap_or(0.0);
+
+        let mut out: Vec<(u32, f32)> = heap.into_iter().map(|Reverse(s)| (s.id, s.score)).collect();
+        out.sort_unstable_by(|a, b| {
+            b.1.partial_cmp(&a.1)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| a.0.cmp(&b.0))
+        });
+        out
+    }
+
+    /// The k-th best score from the most recent `finalize_topk` call (the
+    /// current min-heap root), or `0.0` if it hasn't run yet for this
+    /// accumulation. When that call saw fewer than k touched ids, this is the
+    /// lowest of their scores rather than a k-th best. Lets scoring code skip `update_max`/`set_score` for ids
+    /// that provably can't enter the top-k (MaxScore/WAND-style early exit).
+    #[inline(always)]
+    pub fn kth_threshold(&self) -> f32 {
+        self.topk_threshold
+    }
 }
 impl Default for Accum {
     fn default() -> Self {
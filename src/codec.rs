@@ -0,0 +1,114 @@
+// codec.rs — shared binary (de)serialization primitives, hand-rolled and
+// little-endian throughout (no serde), matching the wire formats already
+// used by wire.rs/storage.rs/ingest.rs.
+
+use std::io::{self, Read, Write};
+
+pub trait ToWriter {
+    fn write_to<W: Write>(&self, w: &mut W) -> io::Result<()>;
+}
+
+pub trait FromReader: Sized {
+    fn read_from<R: Read>(r: &mut R) -> io::Result<Self>;
+}
+
+macro_rules! impl_codec_for_int {
+    ($t:ty) => {
+        impl ToWriter for $t {
+            #[inline]
+            fn write_to<W: Write>(&self, w: &mut W) -> io::Result<()> {
+                w.write_all(&self.to_le_bytes())
+            }
+        }
+        impl FromReader for $t {
+            #[inline]
+            fn read_from<R: Read>(r: &mut R) -> io::Result<Self> {
+                let mut buf = [0u8; std::mem::size_of::<$t>()];
+                r.read_exact(&mut buf)?;
+                Ok(<$t>::from_le_bytes(buf))
+            }
+        }
+    };
+}
+impl_codec_for_int!(u8);
+impl_codec_for_int!(u16);
+impl_codec_for_int!(u32);
+impl_codec_for_int!(u64);
+
+pub fn write_bytes_u32<W: Write>(w: &mut W, bytes: &[u8]) -> io::Result<()> {
+    (bytes.len() as u32).write_to(w)?;
+    w.write_all(bytes)
+}
+
+pub fn read_bytes_u32<R: Read>(r: &mut R) -> io::Result<Vec<u8>> {
+    let len = u32::read_from(r)? as usize;
+    let mut buf = vec![0u8; len];
+    r.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+pub fn write_u32_vec<W: Write>(w: &mut W, v: &[u32]) -> io::Result<()> {
+    (v.len() as u32).write_to(w)?;
+    for &x in v {
+        x.write_to(w)?;
+    }
+    Ok(())
+}
+
+pub fn read_u32_vec<R: Read>(r: &mut R) -> io::Result<Vec<u32>> {
+    let len = u32::read_from(r)? as usize;
+    let mut out = Vec::with_capacity(len);
+    for _ in 0..len {
+        out.push(u32::read_from(r)?);
+    }
+    Ok(out)
+}
+
+pub fn write_u16_vec<W: Write>(w: &mut W, v: &[u16]) -> io::Result<()> {
+    (v.len() as u32).write_to(w)?;
+    for &x in v {
+        x.write_to(w)?;
+    }
+    Ok(())
+}
+
+pub fn read_u16_vec<R: Read>(r: &mut R) -> io::Result<Vec<u16>> {
+    let len = u32::read_from(r)? as usize;
+    let mut out = Vec::with_capacity(len);
+    for _ in 0..len {
+        out.push(u16::read_from(r)?);
+    }
+    Ok(out)
+}
+
+pub fn bad_data(msg: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.into())
+}
+
+/// LEB128 unsigned varint, for fields whose length shouldn't be capped by a
+/// fixed-width integer (e.g. storage.rs's pack record lengths, which used to
+/// be hard-coded as u16 and silently truncated anything over 64 KiB).
+pub fn write_varint<W: Write>(w: &mut W, mut v: u64) -> io::Result<()> {
+    loop {
+        let byte = (v & 0x7f) as u8;
+        v >>= 7;
+        if v == 0 {
+            return w.write_all(&[byte]);
+        }
+        w.write_all(&[byte | 0x80])?;
+    }
+}
+
+pub fn read_varint<R: Read>(r: &mut R) -> io::Result<u64> {
+    let mut result: u64 = 0;
+    let mut shift = 0u32;
+    loop {
+        let mut b = [0u8; 1];
+        r.read_exact(&mut b)?;
+        result |= ((b[0] & 0x7f) as u64) << shift;
+        if b[0] & 0x80 == 0 {
+            return Ok(result);
+        }
+        shift += 7;
+    }
+}
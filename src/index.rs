@@ -1,8 +1,18 @@
 use std::cmp::Reverse;
 use std::collections::BinaryHeap;
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 
+use memmap2::Mmap;
+
 use crate::accum::Accum;
+use crate::codec::{
+    bad_data, read_bytes_u32, read_u16_vec, read_u32_vec, write_bytes_u32, write_u16_vec,
+    write_u32_vec, FromReader, ToWriter,
+};
 use crate::qgram::sig4096_from_text; // used by IndexBuilder
 use crate::simd::{overlap_popcnt, popcnt4, prefetch_sig};
 use crate::wire::{Hit, Query256, FLAG_FUZZY_JACCARD};
@@ -17,28 +27,472 @@ pub struct DocMeta {
     pub uri: Arc<str>,
 }
 
+// ---------- roaring-style compressed postings ----------
+//
+// Large lanes (high bit_freq, common prefix buckets) can hold a big chunk of
+// a segment's rows as one flat Vec<u32>; that's wasteful to store and slow to
+// intersect lane-by-lane. Partition each row id into a high 16 bits (the
+// "chunk" key) and low 16 bits, and store each chunk's members as either a
+// sorted Vec<u16> (cheap when sparse) or a fixed 8 KiB bitmap (cheap when
+// dense) — the same array/bitmap split roaring bitmaps use.
+const ARRAY_MAX_CARD: usize = 4096;
+const BITMAP_WORDS: usize = 1024; // 1024 * 64 bits = 65536 = one chunk's id space
+
+#[derive(Clone)]
+enum Container {
+    Array(Vec<u16>),
+    Bitmap(Box<[u64; BITMAP_WORDS]>),
+}
+
+impl Container {
+    fn cardinality(&self) -> usize {
+        match self {
+            Container::Array(v) => v.len(),
+            Container::Bitmap(bm) => bm.iter().map(|w| w.count_ones() as usize).sum(),
+        }
+    }
+    #[inline]
+    fn contains(&self, lo: u16) -> bool {
+        match self {
+            Container::Array(v) => v.binary_search(&lo).is_ok(),
+            Container::Bitmap(bm) => (bm[(lo >> 6) as usize] >> (lo & 63)) & 1 != 0,
+        }
+    }
+    fn from_sorted_lows(lows: &[u16]) -> Self {
+        if lows.len() <= ARRAY_MAX_CARD {
+            Container::Array(lows.to_vec())
+        } else {
+            let mut bm = Box::new([0u64; BITMAP_WORDS]);
+            for &lo in lows {
+                bm[(lo >> 6) as usize] |= 1u64 << (lo & 63);
+            }
+            Container::Bitmap(bm)
+        }
+    }
+    fn for_each(&self, mut f: impl FnMut(u16)) {
+        match self {
+            Container::Array(v) => v.iter().for_each(|&lo| f(lo)),
+            Container::Bitmap(bm) => {
+                for (wi, &w) in bm.iter().enumerate() {
+                    let mut w = w;
+                    while w != 0 {
+                        let b = w.trailing_zeros();
+                        f(((wi as u16) << 6) | b as u16);
+                        w &= w - 1;
+                    }
+                }
+            }
+        }
+    }
+    // intersect two containers, appending matched low bits to `out` (capped)
+    fn intersect_into(&self, other: &Container, cap: usize, out: &mut Vec<u16>) {
+        match (self, other) {
+            (Container::Array(a), Container::Array(b)) => {
+                let (mut i, mut j) = (0usize, 0usize);
+                while i < a.len() && j < b.len() && out.len() < cap {
+                    match a[i].cmp(&b[j]) {
+                        std::cmp::Ordering::Equal => {
+                            out.push(a[i]);
+                            i += 1;
+                            j += 1;
+                        }
+                        std::cmp::Ordering::Less => i += 1,
+                        std::cmp::Ordering::Greater => j += 1,
+                    }
+                }
+            }
+            (Container::Array(a), Container::Bitmap(bm)) | (Container::Bitmap(bm), Container::Array(a)) => {
+                for &lo in a {
+                    if out.len() >= cap {
+                        break;
+                    }
+                    if (bm[(lo >> 6) as usize] >> (lo & 63)) & 1 != 0 {
+                        out.push(lo);
+                    }
+                }
+            }
+            (Container::Bitmap(a), Container::Bitmap(b)) => {
+                'words: for wi in 0..BITMAP_WORDS {
+                    let mut w = a[wi] & b[wi];
+                    while w != 0 {
+                        if out.len() >= cap {
+                            break 'words;
+                        }
+                        let b0 = w.trailing_zeros();
+                        out.push(((wi as u16) << 6) | b0 as u16);
+                        w &= w - 1;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Sorted-by-high-key list of per-chunk containers over a set of `u32` row
+/// ids. Intersection skips whole 64K ranges whose high keys don't align,
+/// instead of walking every id.
+#[derive(Clone, Default)]
+pub struct Postings {
+    chunks: Vec<(u16, Container)>,
+}
+
+impl Postings {
+    pub fn from_sorted_u32(ids: &[u32]) -> Self {
+        let mut chunks = Vec::new();
+        let mut i = 0usize;
+        while i < ids.len() {
+            let high = (ids[i] >> 16) as u16;
+            let start = i;
+            while i < ids.len() && (ids[i] >> 16) as u16 == high {
+                i += 1;
+            }
+            let lows: Vec<u16> = ids[start..i].iter().map(|&x| (x & 0xFFFF) as u16).collect();
+            chunks.push((high, Container::from_sorted_lows(&lows)));
+        }
+        Self { chunks }
+    }
+    pub fn len(&self) -> usize {
+        self.chunks.iter().map(|(_, c)| c.cardinality()).sum()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+    pub fn clear(&mut self) {
+        self.chunks.clear();
+    }
+    /// Reset to a clone of `other`'s chunk list, reusing this `Postings`'
+    /// already-allocated outer `Vec`.
+    pub fn copy_from(&mut self, other: &Postings) {
+        self.chunks.clear();
+        self.chunks.extend(other.chunks.iter().cloned());
+    }
+    #[inline]
+    pub fn contains(&self, id: u32) -> bool {
+        let high = (id >> 16) as u16;
+        let lo = (id & 0xFFFF) as u16;
+        match self.chunks.binary_search_by_key(&high, |(h, _)| *h) {
+            Ok(idx) => self.chunks[idx].1.contains(lo),
+            Err(_) => false,
+        }
+    }
+    pub fn iter(&self) -> impl Iterator<Item = u32> + '_ {
+        self.chunks.iter().flat_map(|&(high, ref c)| {
+            let mut lows = Vec::with_capacity(c.cardinality());
+            c.for_each(|lo| lows.push(lo));
+            lows.into_iter().map(move |lo| ((high as u32) << 16) | lo as u32)
+        })
+    }
+    /// Copy up to `cap` ids (ascending) into `out`, to seed a scratch
+    /// candidate buffer from this posting list.
+    pub fn seed_into(&self, out: &mut Vec<u32>, cap: usize) {
+        for id in self.iter() {
+            if out.len() >= cap {
+                break;
+            }
+            out.push(id);
+        }
+    }
+    /// Intersect with `other`, writing matched ids (ascending, capped at
+    /// `cap` total) into `out` as a fresh `Postings` (chunk lists merge-join
+    /// on the high key, so chunks that can't overlap are skipped entirely).
+    pub fn intersect_into(&self, other: &Postings, cap: usize, out: &mut Postings) {
+        out.chunks.clear();
+        let (mut i, mut j) = (0usize, 0usize);
+        let mut total = 0usize;
+        let mut lows_buf: Vec<u16> = Vec::new();
+        while i < self.chunks.len() && j < other.chunks.len() && total < cap {
+            let (ha, ca) = &self.chunks[i];
+            let (hb, cb) = &other.chunks[j];
+            match ha.cmp(hb) {
+                std::cmp::Ordering::Less => i += 1,
+                std::cmp::Ordering::Greater => j += 1,
+                std::cmp::Ordering::Equal => {
+                    lows_buf.clear();
+                    ca.intersect_into(cb, cap - total, &mut lows_buf);
+                    if !lows_buf.is_empty() {
+                        total += lows_buf.len();
+                        out.chunks.push((*ha, Container::from_sorted_lows(&lows_buf)));
+                    }
+                    i += 1;
+                    j += 1;
+                }
+            }
+        }
+    }
+}
+
+impl ToWriter for Container {
+    fn write_to<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        match self {
+            Container::Array(v) => {
+                0u8.write_to(w)?;
+                write_u16_vec(w, v)
+            }
+            Container::Bitmap(bm) => {
+                1u8.write_to(w)?;
+                for &word in bm.iter() {
+                    word.write_to(w)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+impl FromReader for Container {
+    fn read_from<R: Read>(r: &mut R) -> io::Result<Self> {
+        match u8::read_from(r)? {
+            0 => Ok(Container::Array(read_u16_vec(r)?)),
+            1 => {
+                let mut bm = Box::new([0u64; BITMAP_WORDS]);
+                for word in bm.iter_mut() {
+                    *word = u64::read_from(r)?;
+                }
+                Ok(Container::Bitmap(bm))
+            }
+            tag => Err(bad_data(format!("unknown posting container tag {tag}"))),
+        }
+    }
+}
+
+impl ToWriter for Postings {
+    fn write_to<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        (self.chunks.len() as u32).write_to(w)?;
+        for (high, c) in &self.chunks {
+            high.write_to(w)?;
+            c.write_to(w)?;
+        }
+        Ok(())
+    }
+}
+impl FromReader for Postings {
+    fn read_from<R: Read>(r: &mut R) -> io::Result<Self> {
+        let n = u32::read_from(r)? as usize;
+        let mut chunks = Vec::with_capacity(n);
+        for _ in 0..n {
+            let high = u16::read_from(r)?;
+            chunks.push((high, Container::read_from(r)?));
+        }
+        Ok(Self { chunks })
+    }
+}
+
+/// Backing storage for one 64-bit signature lane: either freshly built and
+/// owned, or a zero-copy view into a memory-mapped `.seg` file (see
+/// `Segment::write_to`/`read_from`/`IndexView::open_mmap`).
+#[derive(Clone)]
+pub enum Lane {
+    Owned(Arc<Vec<u64>>),
+    Mapped { mmap: Arc<Mmap>, range: std::ops::Range<usize> },
+}
+impl Lane {
+    #[inline]
+    pub fn as_slice(&self) -> &[u64] {
+        match self {
+            Lane::Owned(v) => v,
+            Lane::Mapped { mmap, range } => {
+                let bytes = &mmap[range.clone()];
+                debug_assert_eq!(bytes.len() % 8, 0);
+                // SAFETY: `write_to` always starts a lane at an 8-byte
+                // aligned file offset (file offset 0 is page-aligned, and
+                // every section length before it is a multiple of 8), and
+                // the bytes were written as little-endian u64s by the same
+                // function — a valid reinterpretation on the little-endian
+                // hosts this crate targets.
+                unsafe { std::slice::from_raw_parts(bytes.as_ptr() as *const u64, bytes.len() / 8) }
+            }
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct Segment {
     // 256-bit (4×u64) SoA layout — minimal bytes per candidate
-    pub s0: Arc<Vec<u64>>,
-    pub s1: Arc<Vec<u64>>,
-    pub s2: Arc<Vec<u64>>,
-    pub s3: Arc<Vec<u64>>,
+    pub s0: Lane,
+    pub s1: Lane,
+    pub s2: Lane,
+    pub s3: Lane,
     // popcount for light normalization / fuzzy denominator
     pub pop: Arc<Vec<u16>>,
     // per-row metadata (immutable once sealed)
     pub meta: Arc<Vec<DocMeta>>,
     // postings over the first 256 bits
-    pub bit_postings: Arc<[Vec<u32>; 256]>,
+    pub bit_postings: Arc<[Postings; 256]>,
     pub bit_freq: Arc<[u32; 256]>,
 
     // -------- prefix & exact short-token postings --------
     // first-character postings (ASCII-lowered index by byte)
-    pub pref1: Arc<[Vec<u32>; 256]>,
+    pub pref1: Arc<[Postings; 256]>,
     // first-3-chars postings over base36 (a-z0-9) => 36^3 buckets
-    pub pref3: Arc<Vec<Vec<u32>>>, // len = PREF3_SIZE
+    pub pref3: Arc<Vec<Postings>>, // len = PREF3_SIZE
     // exact short tokens (<=6 chars), hashed; entries sorted by key
     pub full6: Arc<Vec<(u64, Vec<u32>)>>,
+
+    // -------- BK-tree typo index --------
+    // distinct searchable tokens + their posting lists, indexed by the same
+    // id the BK-tree nodes use.
+    pub vocab: Arc<Vec<(Box<str>, Vec<u32>)>>,
+    pub bk: Arc<BkTree>,
+
+    // -------- soft deletes --------
+    // one bit per row; set => row is logically deleted and must be skipped
+    // during scoring. Reclaimed for real by `IndexView::compact`.
+    pub tombstones: Arc<Vec<AtomicU64>>,
+}
+
+// ---------- BK-tree over the token vocabulary (real edit-distance typo tolerance) ----------
+
+// Edit-distance tolerance for the last query token: short tokens get a
+// tighter budget so "cat" doesn't fuzzily match half the vocabulary.
+const BK_SHORT_TOKEN_LEN: usize = 5;
+const BK_TOLERANCE_SHORT: u8 = 1;
+const BK_TOLERANCE_LONG: u8 = 2;
+const W_FUZZY_EDIT_PENALTY: f32 = 300.0; // per edit-distance step, subtracted from W_EXACT_LAST
+
+/// A node stores only its outgoing edges; the token itself lives in the
+/// parallel `vocab` array at the same index, so `Segment` can be cloned
+/// cheaply (both are behind `Arc`) without duplicating token bytes.
+#[derive(Clone, Default)]
+pub struct BkNode {
+    // (edit distance to parent, child node index) — small per node, so a
+    // plain Vec beats a HashMap for cache locality and lookups.
+    children: Vec<(u8, u32)>,
+}
+
+#[derive(Clone, Default)]
+pub struct BkTree {
+    root: Option<u32>,
+    nodes: Vec<BkNode>,
+}
+
+impl BkTree {
+    fn build(vocab: &[(Box<str>, Vec<u32>)]) -> Self {
+        let mut nodes = vec![BkNode::default(); vocab.len()];
+        let mut root = None;
+        for i in 0..vocab.len() {
+            let Some(r) = root else {
+                root = Some(i as u32);
+                continue;
+            };
+            let mut cur = r;
+            loop {
+                let d = levenshtein_bounded(vocab[i].0.as_bytes(), vocab[cur as usize].0.as_bytes(), u8::MAX);
+                if d == 0 {
+                    break; // duplicate token text, keep the first node
+                }
+                match nodes[cur as usize].children.iter().find(|&&(dist, _)| dist == d) {
+                    Some(&(_, child)) => cur = child,
+                    None => {
+                        nodes[cur as usize].children.push((d, i as u32));
+                        break;
+                    }
+                }
+            }
+        }
+        Self { root, nodes }
+    }
+
+    /// Collect `(vocab index, edit distance)` for every token within `max_d`
+    /// of `query`, pruning subtrees via the triangle inequality: a child
+    /// reachable only through edge label `dist` can't be within `max_d` of
+    /// `query` unless `|r - dist| <= max_d`, where `r` is `query`'s distance
+    /// to the current node. When `r` is only known to exceed `max_d` (the
+    /// bounded distance returned its sentinel), no upper bound is applied.
+    fn query(&self, vocab: &[(Box<str>, Vec<u32>)], query: &[u8], max_d: u8, out: &mut Vec<(u32, u8)>) {
+        let Some(root) = self.root else { return };
+        let mut stack = vec![root];
+        while let Some(node) = stack.pop() {
+            let r = levenshtein_bounded(query, vocab[node as usize].0.as_bytes(), max_d);
+            if r <= max_d {
+                out.push((node, r));
+            }
+            let lo = r.saturating_sub(max_d);
+            let hi = if r > max_d { u8::MAX } else { r.saturating_add(max_d) };
+            for &(dist, child) in &self.nodes[node as usize].children {
+                if dist >= lo && dist <= hi {
+                    stack.push(child);
+                }
+            }
+        }
+    }
+}
+
+// Sentinel for `BkTree::root == None` — consistent with the `u32::MAX`
+// empty-slot marker `Accum` already uses for its open-addressing table.
+const BK_NO_ROOT: u32 = u32::MAX;
+
+impl ToWriter for BkNode {
+    fn write_to<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        (self.children.len() as u32).write_to(w)?;
+        for &(dist, child) in &self.children {
+            dist.write_to(w)?;
+            child.write_to(w)?;
+        }
+        Ok(())
+    }
+}
+impl FromReader for BkNode {
+    fn read_from<R: Read>(r: &mut R) -> io::Result<Self> {
+        let n = u32::read_from(r)? as usize;
+        let mut children = Vec::with_capacity(n);
+        for _ in 0..n {
+            let dist = u8::read_from(r)?;
+            let child = u32::read_from(r)?;
+            children.push((dist, child));
+        }
+        Ok(Self { children })
+    }
+}
+
+impl ToWriter for BkTree {
+    fn write_to<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        self.root.unwrap_or(BK_NO_ROOT).write_to(w)?;
+        (self.nodes.len() as u32).write_to(w)?;
+        for node in &self.nodes {
+            node.write_to(w)?;
+        }
+        Ok(())
+    }
+}
+impl FromReader for BkTree {
+    fn read_from<R: Read>(r: &mut R) -> io::Result<Self> {
+        let root_raw = u32::read_from(r)?;
+        let root = if root_raw == BK_NO_ROOT { None } else { Some(root_raw) };
+        let n = u32::read_from(r)? as usize;
+        let mut nodes = Vec::with_capacity(n);
+        for _ in 0..n {
+            nodes.push(BkNode::read_from(r)?);
+        }
+        Ok(Self { root, nodes })
+    }
+}
+
+/// Two-row Levenshtein distance with an early exit once every entry in the
+/// current row exceeds `max_d` (the distance can only grow from there), in
+/// which case `max_d + 1` is returned as a "farther than max_d" sentinel.
+fn levenshtein_bounded(a: &[u8], b: &[u8], max_d: u8) -> u8 {
+    let max_d = max_d as usize;
+    let (a, b) = if a.len() <= b.len() { (a, b) } else { (b, a) };
+    if b.len() - a.len() > max_d {
+        return (max_d + 1).min(u8::MAX as usize) as u8;
+    }
+
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut cur: Vec<usize> = vec![0; b.len() + 1];
+
+    for i in 1..=a.len() {
+        cur[0] = i;
+        let mut row_min = cur[0];
+        for j in 1..=b.len() {
+            let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+            cur[j] = (prev[j] + 1).min(cur[j - 1] + 1).min(prev[j - 1] + cost);
+            row_min = row_min.min(cur[j]);
+        }
+        if row_min > max_d {
+            return (max_d + 1).min(u8::MAX as usize) as u8;
+        }
+        std::mem::swap(&mut prev, &mut cur);
+    }
+    prev[b.len()].min(max_d + 1) as u8
 }
 
 const INTERSECT_CAP: usize = 512;
@@ -58,11 +512,387 @@ const W_FUZZY_SCALE: f32 = 100.0;   // fuzzy weight when enabled
 impl Segment {
     #[inline]
     pub fn len(&self) -> usize {
-        self.s0.len()
+        self.s0.as_slice().len()
     }
     #[inline]
     pub fn is_empty(&self) -> bool {
-        self.s0.is_empty()
+        self.s0.as_slice().is_empty()
+    }
+
+    #[inline]
+    fn tombstone_word_count(n_rows: usize) -> usize {
+        (n_rows + 63) / 64
+    }
+    fn new_tombstones(n_rows: usize) -> Arc<Vec<AtomicU64>> {
+        Arc::new((0..Self::tombstone_word_count(n_rows)).map(|_| AtomicU64::new(0)).collect())
+    }
+    #[inline]
+    pub fn is_tombstoned(&self, row: u32) -> bool {
+        let row = row as usize;
+        let word = self.tombstones[row / 64].load(Ordering::Relaxed);
+        (word >> (row % 64)) & 1 != 0
+    }
+    /// Mark `row` as logically deleted. Safe to call concurrently with
+    /// in-flight searches — they'll just stop seeing this row mid-flight.
+    pub fn tombstone(&self, row: u32) {
+        let row = row as usize;
+        self.tombstones[row / 64].fetch_or(1u64 << (row % 64), Ordering::Relaxed);
+    }
+    pub fn tombstone_count(&self) -> usize {
+        self.tombstones.iter().map(|w| w.load(Ordering::Relaxed).count_ones() as usize).sum()
+    }
+    /// Fraction of rows NOT tombstoned; callers compact a segment once this
+    /// drops below some threshold (e.g. 0.5).
+    pub fn live_ratio(&self) -> f32 {
+        let n = self.len();
+        if n == 0 {
+            return 1.0;
+        }
+        1.0 - (self.tombstone_count() as f32 / n as f32)
+    }
+}
+
+// ---------- flat on-disk segment format ----------
+//
+// [magic "SEG1"][version u16][n_rows u32]
+// [section table: 6 x (offset u64, len u64), for s0, s1, s2, s3, pop, bit_freq]
+// [padding to the next 8-byte boundary]
+// [s0 raw u64s][s1][s2][s3][pop, length-prefixed u16s][bit_freq: 256 raw u32s]
+// [tombstones: u32 word count + raw u64 words]
+// [bit_postings x256][pref1 x256][pref3 (u32 count + entries)]
+// [full6][vocab][bk][meta: fixed records + packed utf8 blob]
+//
+// Everything from `bit_postings` on is variable-length and always parsed
+// into owned `Vec`s; only the four fixed-width signature lanes are ever
+// borrowed straight out of a memory mapping.
+
+const SEG_MAGIC: [u8; 4] = *b"SEG1";
+const SEG_VERSION: u16 = 1;
+const SEG_NUM_SECTIONS: usize = 6; // s0, s1, s2, s3, pop, bit_freq
+
+#[inline]
+fn align_up(pos: u64, to: u64) -> u64 {
+    (pos + to - 1) / to * to
+}
+
+/// Everything after the four signature lanes: parsed identically
+/// whether the lanes came from an owned build (`read_from`) or a mapping
+/// (`open_mmap`), so both share this helper instead of duplicating it.
+struct SegmentTail {
+    bit_postings: [Postings; 256],
+    bit_freq: [u32; 256],
+    tombstones: Vec<u64>,
+    pref1: [Postings; 256],
+    pref3: Vec<Postings>,
+    full6: Vec<(u64, Vec<u32>)>,
+    vocab: Vec<(Box<str>, Vec<u32>)>,
+    bk: BkTree,
+    meta: Vec<DocMeta>,
+}
+
+fn read_postings_array256<R: Read>(r: &mut R) -> io::Result<[Postings; 256]> {
+    let mut v: Vec<Postings> = Vec::with_capacity(256);
+    for _ in 0..256 {
+        v.push(Postings::read_from(r)?);
+    }
+    v.try_into().map_err(|_| bad_data("postings array256 length mismatch"))
+}
+
+fn read_segment_tail<R: Read>(r: &mut R) -> io::Result<(Vec<u16>, SegmentTail)> {
+    let pop = read_u16_vec(r)?;
+
+    let mut bit_freq = [0u32; 256];
+    for f in bit_freq.iter_mut() {
+        *f = u32::read_from(r)?;
+    }
+
+    let tombstone_words = u32::read_from(r)? as usize;
+    let mut tombstones = Vec::with_capacity(tombstone_words);
+    for _ in 0..tombstone_words {
+        tombstones.push(u64::read_from(r)?);
+    }
+
+    let bit_postings = read_postings_array256(r)?;
+    let pref1 = read_postings_array256(r)?;
+
+    let pref3_len = u32::read_from(r)? as usize;
+    let mut pref3 = Vec::with_capacity(pref3_len);
+    for _ in 0..pref3_len {
+        pref3.push(Postings::read_from(r)?);
+    }
+
+    let full6_len = u32::read_from(r)? as usize;
+    let mut full6 = Vec::with_capacity(full6_len);
+    for _ in 0..full6_len {
+        let key = u64::read_from(r)?;
+        full6.push((key, read_u32_vec(r)?));
+    }
+
+    let vocab_len = u32::read_from(r)? as usize;
+    let mut vocab = Vec::with_capacity(vocab_len);
+    for _ in 0..vocab_len {
+        let bytes = read_bytes_u32(r)?;
+        let tok = String::from_utf8(bytes)
+            .map_err(|_| bad_data("vocab token is not valid utf8"))?
+            .into_boxed_str();
+        vocab.push((tok, read_u32_vec(r)?));
+    }
+
+    let bk = BkTree::read_from(r)?;
+
+    let meta_len = u32::read_from(r)? as usize;
+    let mut raw_fields: Vec<(u32, [(u32, u32); 5])> = Vec::with_capacity(meta_len);
+    for _ in 0..meta_len {
+        let id = u32::read_from(r)?;
+        let mut fields = [(0u32, 0u32); 5];
+        for f in fields.iter_mut() {
+            f.0 = u32::read_from(r)?;
+            f.1 = u32::read_from(r)?;
+        }
+        raw_fields.push((id, fields));
+    }
+    let blob = read_bytes_u32(r)?;
+    let field_str = |off: u32, len: u32| -> io::Result<Arc<str>> {
+        let bytes = blob
+            .get(off as usize..(off + len) as usize)
+            .ok_or_else(|| bad_data("meta blob offset out of range"))?;
+        let s = std::str::from_utf8(bytes).map_err(|_| bad_data("meta blob is not valid utf8"))?;
+        Ok(Arc::from(s))
+    };
+    let mut meta = Vec::with_capacity(meta_len);
+    for (id, fields) in raw_fields {
+        meta.push(DocMeta {
+            id,
+            title: field_str(fields[0].0, fields[0].1)?,
+            author: field_str(fields[1].0, fields[1].1)?,
+            genres: field_str(fields[2].0, fields[2].1)?,
+            url: field_str(fields[3].0, fields[3].1)?,
+            uri: field_str(fields[4].0, fields[4].1)?,
+        });
+    }
+
+    Ok((
+        pop,
+        SegmentTail { bit_postings, bit_freq, tombstones, pref1, pref3, full6, vocab, bk, meta },
+    ))
+}
+
+impl Segment {
+    /// Write this segment as a flat `.seg` file. See the module-level
+    /// comment above for the exact layout.
+    pub fn write_to<W: Write + Seek>(&self, w: &mut W) -> io::Result<()> {
+        let n_rows = self.len() as u32;
+        w.write_all(&SEG_MAGIC)?;
+        SEG_VERSION.write_to(w)?;
+        n_rows.write_to(w)?;
+
+        // Reserve the section table; patched with real offsets once known.
+        let table_pos = w.stream_position()?;
+        for _ in 0..SEG_NUM_SECTIONS {
+            0u64.write_to(w)?;
+            0u64.write_to(w)?;
+        }
+
+        let mut sections = [(0u64, 0u64); SEG_NUM_SECTIONS];
+
+        // Pad up to an 8-byte boundary so the lanes below can be mmap'd back
+        // as `&[u64]` directly.
+        let pos = w.stream_position()?;
+        for _ in pos..align_up(pos, 8) {
+            0u8.write_to(w)?;
+        }
+
+        for (idx, lane) in [&self.s0, &self.s1, &self.s2, &self.s3].into_iter().enumerate() {
+            let slice = lane.as_slice();
+            let off = w.stream_position()?;
+            for &word in slice {
+                word.write_to(w)?;
+            }
+            sections[idx] = (off, (slice.len() * 8) as u64);
+        }
+
+        let off = w.stream_position()?;
+        write_u16_vec(w, &self.pop)?;
+        sections[4] = (off, w.stream_position()? - off);
+
+        let off = w.stream_position()?;
+        for &f in self.bit_freq.iter() {
+            f.write_to(w)?;
+        }
+        sections[5] = (off, w.stream_position()? - off);
+
+        let tombstone_words: Vec<u64> = self.tombstones.iter().map(|a| a.load(Ordering::Relaxed)).collect();
+        (tombstone_words.len() as u32).write_to(w)?;
+        for word in tombstone_words {
+            word.write_to(w)?;
+        }
+
+        for p in self.bit_postings.iter() {
+            p.write_to(w)?;
+        }
+        for p in self.pref1.iter() {
+            p.write_to(w)?;
+        }
+        (self.pref3.len() as u32).write_to(w)?;
+        for p in self.pref3.iter() {
+            p.write_to(w)?;
+        }
+
+        (self.full6.len() as u32).write_to(w)?;
+        for (key, v) in self.full6.iter() {
+            key.write_to(w)?;
+            write_u32_vec(w, v)?;
+        }
+
+        (self.vocab.len() as u32).write_to(w)?;
+        for (tok, v) in self.vocab.iter() {
+            write_bytes_u32(w, tok.as_bytes())?;
+            write_u32_vec(w, v)?;
+        }
+
+        self.bk.write_to(w)?;
+
+        (self.meta.len() as u32).write_to(w)?;
+        let mut blob: Vec<u8> = Vec::new();
+        for m in self.meta.iter() {
+            m.id.write_to(w)?;
+            for field in [&m.title, &m.author, &m.genres, &m.url, &m.uri] {
+                let field_off = blob.len() as u32;
+                blob.extend_from_slice(field.as_bytes());
+                field_off.write_to(w)?;
+                (field.len() as u32).write_to(w)?;
+            }
+        }
+        write_bytes_u32(w, &blob)?;
+
+        let end_pos = w.stream_position()?;
+        w.seek(SeekFrom::Start(table_pos))?;
+        for (off, len) in sections {
+            off.write_to(w)?;
+            len.write_to(w)?;
+        }
+        w.seek(SeekFrom::Start(end_pos))?;
+        Ok(())
+    }
+
+    /// Read a segment back sequentially (no mmap) — rebuilds every section
+    /// as an owned `Vec`. For zero-copy loading of the signature lanes, use
+    /// `IndexView::open_mmap` instead.
+    pub fn read_from<R: Read + Seek>(r: &mut R) -> io::Result<Segment> {
+        let mut magic = [0u8; 4];
+        r.read_exact(&mut magic)?;
+        if magic != SEG_MAGIC {
+            return Err(bad_data("bad segment magic"));
+        }
+        let version = u16::read_from(r)?;
+        if version != SEG_VERSION {
+            return Err(bad_data(format!("unsupported segment version {version}")));
+        }
+        let n_rows = u32::read_from(r)? as usize;
+
+        let mut sections = [(0u64, 0u64); SEG_NUM_SECTIONS];
+        for s in sections.iter_mut() {
+            s.0 = u64::read_from(r)?;
+            s.1 = u64::read_from(r)?;
+        }
+
+        let pos = r.stream_position()?;
+        r.seek(SeekFrom::Start(align_up(pos, 8)))?;
+
+        let mut lanes: [Vec<u64>; 4] = Default::default();
+        for (i, lane) in lanes.iter_mut().enumerate() {
+            if sections[i].1 as usize != n_rows * 8 {
+                return Err(bad_data("segment lane length doesn't match n_rows"));
+            }
+            lane.reserve(n_rows);
+            for _ in 0..n_rows {
+                lane.push(u64::read_from(r)?);
+            }
+        }
+        let [s0, s1, s2, s3] = lanes;
+
+        let (pop, tail) = read_segment_tail(r)?;
+
+        Ok(Segment {
+            s0: Lane::Owned(Arc::new(s0)),
+            s1: Lane::Owned(Arc::new(s1)),
+            s2: Lane::Owned(Arc::new(s2)),
+            s3: Lane::Owned(Arc::new(s3)),
+            pop: Arc::new(pop),
+            meta: Arc::new(tail.meta),
+            bit_postings: Arc::new(tail.bit_postings),
+            bit_freq: Arc::new(tail.bit_freq),
+            pref1: Arc::new(tail.pref1),
+            pref3: Arc::new(tail.pref3),
+            full6: Arc::new(tail.full6),
+            vocab: Arc::new(tail.vocab),
+            bk: Arc::new(tail.bk),
+            tombstones: Arc::new(tail.tombstones.into_iter().map(AtomicU64::new).collect()),
+        })
+    }
+
+    /// Memory-map a `.seg` file and build a `Segment` whose four signature
+    /// lanes (`s0..s3`) are zero-copy views into the mapping; everything
+    /// else is parsed into owned data exactly as `read_from` does.
+    pub fn open_mmap(path: impl AsRef<Path>) -> io::Result<Segment> {
+        let file = File::open(path)?;
+        let mmap = Arc::new(unsafe { Mmap::map(&file)? });
+
+        let mut cursor = io::Cursor::new(&mmap[..]);
+        let mut magic = [0u8; 4];
+        cursor.read_exact(&mut magic)?;
+        if magic != SEG_MAGIC {
+            return Err(bad_data("bad segment magic"));
+        }
+        let version = u16::read_from(&mut cursor)?;
+        if version != SEG_VERSION {
+            return Err(bad_data(format!("unsupported segment version {version}")));
+        }
+        let n_rows = u32::read_from(&mut cursor)? as usize;
+
+        let mut sections = [(0u64, 0u64); SEG_NUM_SECTIONS];
+        for s in sections.iter_mut() {
+            s.0 = u64::read_from(&mut cursor)?;
+            s.1 = u64::read_from(&mut cursor)?;
+        }
+
+        let mut lanes: [Lane; 4] = std::array::from_fn(|_| Lane::Owned(Arc::new(Vec::new())));
+        for i in 0..4 {
+            let (off, len) = sections[i];
+            if len as usize != n_rows * 8 {
+                return Err(bad_data("segment lane length doesn't match n_rows"));
+            }
+            let range = off as usize..(off + len) as usize;
+            if mmap.get(range.clone()).is_none() {
+                return Err(bad_data("segment lane range out of bounds"));
+            }
+            lanes[i] = Lane::Mapped { mmap: mmap.clone(), range };
+        }
+        let [s0, s1, s2, s3] = lanes;
+
+        // `pop` immediately follows the last lane (`s3`) in file order but
+        // is addressed by the section table too, so seek there directly
+        // rather than assuming the tail starts right after the lanes.
+        let (pop_off, _) = sections[4];
+        cursor.seek(SeekFrom::Start(pop_off))?;
+        let (pop, tail) = read_segment_tail(&mut cursor)?;
+
+        Ok(Segment {
+            s0,
+            s1,
+            s2,
+            s3,
+            pop: Arc::new(pop),
+            meta: Arc::new(tail.meta),
+            bit_postings: Arc::new(tail.bit_postings),
+            bit_freq: Arc::new(tail.bit_freq),
+            pref1: Arc::new(tail.pref1),
+            pref3: Arc::new(tail.pref3),
+            full6: Arc::new(tail.full6),
+            vocab: Arc::new(tail.vocab),
+            bk: Arc::new(tail.bk),
+            tombstones: Arc::new(tail.tombstones.into_iter().map(AtomicU64::new).collect()),
+        })
     }
 }
 
@@ -99,7 +929,10 @@ fn with_qtext<R>(f: impl FnOnce(&str) -> R) -> R {
 struct Scratch {
     qbits: Vec<u16>,
     cand: Vec<u32>,
-    tmp: Vec<u32>,
+    // roaring-style scratch used while pruning candidates; `tmp_postings` is
+    // the ping-pong buffer `cand_postings` intersects into each round.
+    cand_postings: Postings,
+    tmp_postings: Postings,
     qbuf: String, // normalized query text reuse
 }
 impl Scratch {
@@ -107,7 +940,8 @@ impl Scratch {
         Self {
             qbits: Vec::with_capacity(64),
             cand: Vec::with_capacity(INTERSECT_CAP),
-            tmp: Vec::with_capacity(INTERSECT_CAP),
+            cand_postings: Postings::default(),
+            tmp_postings: Postings::default(),
             qbuf: String::with_capacity(256),
         }
     }
@@ -214,15 +1048,87 @@ fn contains_sorted(v: &[u32], x: u32) -> bool {
 #[derive(Clone)]
 pub struct IndexView {
     pub segments: Arc<[Arc<Segment>]>,
+    // reverse global-id -> (seg index, row) lookup, rebuilt whenever the
+    // segment set changes; a doc re-ingested across seals can legitimately
+    // appear more than once until the next `compact()`, so every location
+    // needs to be tombstoned together.
+    id_index: Arc<std::collections::HashMap<u32, Vec<(u16, u32)>>>,
 }
 impl IndexView {
     pub fn from_segments(segments: Vec<Arc<Segment>>) -> Self {
-        Self { segments: segments.into() }
+        let mut id_index: std::collections::HashMap<u32, Vec<(u16, u32)>> = std::collections::HashMap::new();
+        for (seg_idx, seg) in segments.iter().enumerate() {
+            for (row, m) in seg.meta.iter().enumerate() {
+                id_index.entry(m.id).or_default().push((seg_idx as u16, row as u32));
+            }
+        }
+        Self { segments: segments.into(), id_index: Arc::new(id_index) }
     }
     pub fn total_docs(&self) -> usize {
         self.segments.iter().map(|s| s.len()).sum()
     }
 
+    /// Tombstone every live row carrying `global_id`, across every segment
+    /// it appears in. Returns `false` if the id isn't present at all.
+    pub fn delete(&self, global_id: u32) -> bool {
+        match self.id_index.get(&global_id) {
+            Some(locs) if !locs.is_empty() => {
+                for &(seg_idx, row) in locs {
+                    self.segments[seg_idx as usize].tombstone(row);
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Merge every segment's live (non-tombstoned) rows into one fresh
+    /// segment, keeping only the newest row per `DocMeta.id` (rows from a
+    /// later segment win ties, since re-ingests seal into later segments).
+    /// Returns a brand-new `IndexView`; callers publish it the same way a
+    /// freshly-sealed segment is published (e.g. via `ArcSwap::rcu`).
+    pub fn compact(&self) -> IndexView {
+        let mut newest: std::collections::HashMap<u32, (usize, u32)> = std::collections::HashMap::new();
+        for (seg_idx, seg) in self.segments.iter().enumerate() {
+            for row in 0..seg.len() as u32 {
+                if seg.is_tombstoned(row) {
+                    continue;
+                }
+                let id = seg.meta[row as usize].id;
+                newest.insert(id, (seg_idx, row)); // later segment overwrites earlier ones
+            }
+        }
+
+        let mut ordered: Vec<(u32, (usize, u32))> = newest.into_iter().collect();
+        ordered.sort_unstable_by_key(|&(id, _)| id);
+
+        let mut signatures_aos = Vec::with_capacity(ordered.len());
+        let mut pop = Vec::with_capacity(ordered.len());
+        let mut meta = Vec::with_capacity(ordered.len());
+        for (_, (seg_idx, row)) in ordered {
+            let seg = &self.segments[seg_idx];
+            let row = row as usize;
+            signatures_aos.push([
+                seg.s0.as_slice()[row],
+                seg.s1.as_slice()[row],
+                seg.s2.as_slice()[row],
+                seg.s3.as_slice()[row],
+            ]);
+            pop.push(seg.pop[row]);
+            meta.push(seg.meta[row].clone());
+        }
+
+        let merged = build_segment_from_rows(signatures_aos, pop, meta);
+        IndexView::from_segments(vec![Arc::new(merged)])
+    }
+
+    /// Build a single-segment view by memory-mapping a `.seg` file written
+    /// by `Segment::write_to`, instead of rebuilding one from raw records.
+    pub fn open_mmap(path: impl AsRef<std::path::Path>) -> io::Result<Self> {
+        let seg = Segment::open_mmap(path)?;
+        Ok(Self::from_segments(vec![Arc::new(seg)]))
+    }
+
     // main.rs sets TLS via with_query_text(...).
     pub fn search(&self, q: Query256) -> Vec<Hit> {
         if self.segments.is_empty() {
@@ -268,7 +1174,6 @@ impl Segment {
             let mut sc = scratch.borrow_mut();
             sc.qbits.clear();
             sc.cand.clear();
-            sc.tmp.clear();
             sc.qbuf.clear();
 
             // ---- 0) Normalize raw query text once (no clones) ----
@@ -306,16 +1211,18 @@ impl Segment {
             let grams_count = sc.qbits.len();
 
             // ---- 2) MUST set & exact slices ----
-            let mut must_slice: &[u32] = &[];
+            let mut must_slice: Option<&Postings> = None;
             let mut last_prefix_len: usize = 0;
             if let Some(ref l) = last_owned {
                 last_prefix_len = l.len();
                 if l.len() >= 3 {
                     if let Some(idx) = pref3_index(l[0], l[1], l[2]) {
-                        must_slice = unsafe { self.pref3.get_unchecked(idx) };
+                        let p = unsafe { self.pref3.get_unchecked(idx) };
+                        if !p.is_empty() { must_slice = Some(p); }
                     }
                 } else if l.len() >= 1 {
-                    must_slice = unsafe { self.pref1.get_unchecked(l[0] as usize) };
+                    let p = unsafe { self.pref1.get_unchecked(l[0] as usize) };
+                    if !p.is_empty() { must_slice = Some(p); }
                 }
             }
 
@@ -337,61 +1244,117 @@ impl Segment {
                 }
             }
 
-            // ---- 3) Seed candidates ----
-            if !must_slice.is_empty() {
-                extend_cap(&mut sc.cand, must_slice, INTERSECT_CAP);
+            // ---- 2b) Typo-tolerant fallback for the last token via the BK-tree.
+            // Only kicks in when there's no exact short-token match. `full6`
+            // only holds tokens of <= 6 chars, so a longer last token always
+            // takes this path, even when it is spelled correctly.
+            let mut bk_matches: Vec<(u32, u8)> = Vec::new();
+            if last_exact_slice.is_none() {
+                if let Some(ref l) = last_owned {
+                    if l.len() >= 2 {
+                        let tol = if l.len() <= BK_SHORT_TOKEN_LEN {
+                            BK_TOLERANCE_SHORT
+                        } else {
+                            BK_TOLERANCE_LONG
+                        };
+                        self.bk.query(&self.vocab, l, tol, &mut bk_matches);
+                        bk_matches.sort_unstable_by_key(|&(_, d)| d);
+                        bk_matches.truncate(8);
+                    }
+                }
+            }
+
+            // ---- 3) Seed candidates (roaring postings) ----
+            sc.cand_postings.clear();
+            if let Some(ms) = must_slice {
+                sc.cand_postings.copy_from(ms);
             } else if !sc.qbits.is_empty() {
                 let rare0 = sc.qbits[0] as usize;
                 if self.bit_freq[rare0] != 0 {
-                    sc.cand.extend(self.bit_postings[rare0].iter().take(INTERSECT_CAP).copied());
+                    sc.cand_postings.copy_from(&self.bit_postings[rare0]);
                 }
             }
-            if sc.cand.is_empty() { return; }
+            if sc.cand_postings.is_empty() && bk_matches.is_empty() { return; }
 
-            // Optional prune with a couple more rare bits
+            // Optional prune with a couple more rare bits — advances two
+            // chunk lists by high key, so ranges that can't overlap are
+            // skipped instead of being walked id-by-id.
             let rare_bits_needed = if grams_count < 5 { 1 } else if grams_count < 10 { 2 } else { 3 };
             for bit_idx in 1..rare_bits_needed.min(sc.qbits.len()) {
-                if sc.cand.is_empty() { break; }
+                if sc.cand_postings.is_empty() { break; }
                 let bit = sc.qbits[bit_idx] as usize;
                 if self.bit_freq[bit] != 0 {
-                    // ---- FIX 1: move tmp out, use it, move back ----
-                    let mut tmp_local = std::mem::take(&mut sc.tmp);
-                    intersect_in_place_bounded_tmp(&mut sc.cand, &self.bit_postings[bit], INTERSECT_CAP, &mut tmp_local);
-                    sc.tmp = tmp_local;
+                    let mut tmp_local = std::mem::take(&mut sc.tmp_postings);
+                    sc.cand_postings.intersect_into(&self.bit_postings[bit], INTERSECT_CAP, &mut tmp_local);
+                    sc.cand_postings = tmp_local;
                 }
             }
 
             // Hard enforce MUST (if set)
-            if !must_slice.is_empty() {
-                // ---- FIX 2: same trick here ----
-                let mut tmp_local = std::mem::take(&mut sc.tmp);
-                intersect_in_place_bounded_tmp(&mut sc.cand, must_slice, INTERSECT_CAP, &mut tmp_local);
-                sc.tmp = tmp_local;
-                if sc.cand.is_empty() { return; }
+            if let Some(ms) = must_slice {
+                let mut tmp_local = std::mem::take(&mut sc.tmp_postings);
+                sc.cand_postings.intersect_into(ms, INTERSECT_CAP, &mut tmp_local);
+                sc.cand_postings = tmp_local;
             }
 
+            // Flatten the roaring candidate set into the flat scratch buffer
+            // the scoring loop walks; BK-tree matches (typo fallback — a
+            // different token's postings, not this query's literal
+            // prefix/bit signal) are unioned in separately so they aren't
+            // filtered out by the MUST/bit pruning above.
+            let roaring_ids: Vec<u32> = sc.cand_postings.iter().collect();
+            sc.cand.clear();
+            sc.cand.extend(roaring_ids);
+            for &(vid, _) in &bk_matches {
+                extend_cap(&mut sc.cand, &self.vocab[vid as usize].1, INTERSECT_CAP);
+            }
+            if !bk_matches.is_empty() {
+                sc.cand.sort_unstable();
+                sc.cand.dedup();
+            }
+            if sc.cand.is_empty() { return; }
+
             // ---- 4) Score candidates ----
             let qsig = [q.sig[0], q.sig[1], q.sig[2], q.sig[3]];
             let qpop = popcnt4(&qsig) as f32;
             let is_fuzzy = (q.flags & FLAG_FUZZY_JACCARD) != 0;
 
+            // Upper bound on any per-row bonus beyond the base overlap score,
+            // used below to skip rows that provably can't make the top-k once
+            // `acc.kth_threshold()` has been established.
+            let max_extra_boost = 10.0 * W_PREFIX_PER_CHAR
+                + W_EXACT_LAST
+                + W_EXACT_ANY
+                + if is_fuzzy { W_FUZZY_SCALE } else { 0.0 };
+            const REFRESH_EVERY: usize = 2048;
+
+            // Only valid once a `finalize_topk` call has actually seen >= k
+            // touched rows — before that, `kth_threshold()` is the min score
+            // of a not-yet-full heap and would wrongly prune real candidates.
+            let mut pruning_active = false;
+
             ACCUM_BEST.with(|acc| {
                 let mut acc = acc.borrow_mut();
                 acc.begin();
 
+                let (s0, s1, s2, s3) = (self.s0.as_slice(), self.s1.as_slice(), self.s2.as_slice(), self.s3.as_slice());
                 for (i, &row32) in sc.cand.iter().enumerate() {
                     let row = row32 as usize;
 
                     if i + 1 < sc.cand.len() {
                         let next_row = sc.cand[i + 1] as usize;
-                        unsafe { prefetch_sig(self.s0.as_ptr().add(next_row)); }
+                        unsafe { prefetch_sig(s0.as_ptr().add(next_row)); }
+                    }
+
+                    if self.is_tombstoned(row32) {
+                        continue;
                     }
 
                     let sig = [
-                        unsafe { *self.s0.get_unchecked(row) },
-                        unsafe { *self.s1.get_unchecked(row) },
-                        unsafe { *self.s2.get_unchecked(row) },
-                        unsafe { *self.s3.get_unchecked(row) },
+                        unsafe { *s0.get_unchecked(row) },
+                        unsafe { *s1.get_unchecked(row) },
+                        unsafe { *s2.get_unchecked(row) },
+                        unsafe { *s3.get_unchecked(row) },
                     ];
                     let inter = overlap_popcnt(&sig, &qsig) as f32;
                     if inter <= 0.0 { continue; }
@@ -400,16 +1363,33 @@ impl Segment {
                     let mut score_val = inter / (1.0 + 0.02 * pb);
                     score_val += W_BOUNDARY.min(inter * 0.02);
 
-                    if last_prefix_len > 0 && !must_slice.is_empty() {
-                        if contains_sorted(must_slice, row32) {
-                            let l = last_prefix_len as f32;
-                            score_val += (l.min(10.0)) * W_PREFIX_PER_CHAR;
+                    // MaxScore/WAND-style early exit: once the top-k heap is
+                    // actually full, a row whose best possible score (base +
+                    // every bonus maxed out) still can't beat its threshold
+                    // can't enter the final top-k, so skip scoring it further.
+                    if pruning_active && score_val + max_extra_boost < acc.kth_threshold() {
+                        continue;
+                    }
+
+                    if last_prefix_len > 0 {
+                        if let Some(ms) = must_slice {
+                            if ms.contains(row32) {
+                                let l = last_prefix_len as f32;
+                                score_val += (l.min(10.0)) * W_PREFIX_PER_CHAR;
+                            }
                         }
                     }
                     if let Some(v) = last_exact_slice {
                         if contains_sorted(v, row32) {
                             score_val += W_EXACT_LAST;
                         }
+                    } else if !bk_matches.is_empty() {
+                        for &(vid, dist) in &bk_matches {
+                            if contains_sorted(&self.vocab[vid as usize].1, row32) {
+                                score_val += (W_EXACT_LAST - dist as f32 * W_FUZZY_EDIT_PENALTY).max(0.0);
+                                break;
+                            }
+                        }
                     }
                     for oi in 0..ocount {
                         let v = other_exact_slices[oi];
@@ -432,22 +1412,21 @@ impl Segment {
                     } else {
                         acc.update_max(row32, score_val);
                     }
-                }
 
-                // local per-segment cap
-                let mut heap = BinaryHeap::with_capacity(q.k as usize);
-                for row32 in acc.iter_touched() {
-                    let score = acc.get_score(row32);
-                    let h = Hit { seg: seg_id, row: row32, score };
-                    if heap.len() < q.k as usize {
-                        heap.push(Reverse(h));
-                    } else if score > heap.peek().unwrap().0.score {
-                        heap.pop();
-                        heap.push(Reverse(h));
+                    // Refresh the threshold periodically so later candidates
+                    // benefit from what's already been scored, instead of
+                    // pruning against a stale (or absent) k-th best for the
+                    // whole pass. Pruning only turns on once the heap is
+                    // actually full of k entries.
+                    if i > 0 && i % REFRESH_EVERY == 0 {
+                        let refreshed = acc.finalize_topk(q.k as usize);
+                        pruning_active = refreshed.len() >= q.k as usize;
                     }
                 }
-                while let Some(Reverse(h)) = heap.pop() {
-                    out.push(h);
+
+                // local per-segment cap
+                for (row32, score) in acc.finalize_topk(q.k as usize) {
+                    out.push(Hit { seg: seg_id, row: row32, score });
                 }
             });
         });
@@ -475,30 +1454,141 @@ fn lookup_full6<'a>(pairs: &'a Vec<(u64, Vec<u32>)>, key: u64) -> Option<&'a [u3
     if lo < pairs.len() && pairs[lo].0 == key { Some(&pairs[lo].1) } else { None }
 }
 
-#[inline]
-fn intersect_in_place_bounded_tmp(out: &mut Vec<u32>, b: &[u32], bound: usize, tmp: &mut Vec<u32>) {
-    if out.is_empty() || b.is_empty() {
-        out.clear();
-        return;
-    }
-    let (mut i, mut j) = (0usize, 0usize);
-    tmp.clear();
-    tmp.reserve(out.len().min(bound));
-    while i < out.len() && j < b.len() && tmp.len() < bound {
-        let x = unsafe { *out.get_unchecked(i) };
-        let y = unsafe { *b.get_unchecked(j) };
-        if x == y {
-            tmp.push(x);
-            i += 1;
-            j += 1;
-        } else if x < y {
-            i += 1;
-        } else {
-            j += 1;
+/// Shared sealing logic: given a fresh (row-aligned) set of signatures,
+/// popcounts, and metadata, rebuild every posting list and return a new
+/// `Segment`. Used both by `IndexBuilder::seal_into_segment` (rows just
+/// ingested) and `IndexView::compact` (rows surviving a merge).
+fn build_segment_from_rows(signatures_aos: Vec<[u64; 4]>, pop: Vec<u16>, meta: Vec<DocMeta>) -> Segment {
+    let n = signatures_aos.len();
+    let mut postings: [Vec<u32>; 256] = std::array::from_fn(|_| Vec::with_capacity(n / 8 + 1));
+
+    for (row, sig) in signatures_aos.iter().enumerate() {
+        for lane in 0..4 {
+            let mut w = sig[lane];
+            while w != 0 {
+                let tz = w.trailing_zeros() as u16;
+                let bit = ((lane as u16) << 6) | tz;
+                postings[bit as usize].push(row as u32);
+                w &= w - 1;
+            }
         }
     }
-    out.clear();
-    out.extend_from_slice(tmp);
+
+    let mut freq = [0u32; 256];
+    for (i, v) in postings.iter_mut().enumerate() {
+        v.sort_unstable();
+        v.dedup();
+        freq[i] = v.len() as u32;
+    }
+    let bit_postings: [Postings; 256] = std::array::from_fn(|i| Postings::from_sorted_u32(&postings[i]));
+
+    let mut s0 = Vec::with_capacity(n);
+    let mut s1 = Vec::with_capacity(n);
+    let mut s2 = Vec::with_capacity(n);
+    let mut s3 = Vec::with_capacity(n);
+    for sig in &signatures_aos {
+        s0.push(sig[0]);
+        s1.push(sig[1]);
+        s2.push(sig[2]);
+        s3.push(sig[3]);
+    }
+
+    // -------- build prefix + exact short-token postings from meta --------
+    let mut pref1: [Vec<u32>; 256] = std::array::from_fn(|_| Vec::new());
+    let mut pref3: Vec<Vec<u32>> = (0..PREF3_SIZE).map(|_| Vec::new()).collect();
+    let mut full6_map: std::collections::HashMap<u64, Vec<u32>> = std::collections::HashMap::new();
+    let mut vocab_map: std::collections::HashMap<Box<str>, Vec<u32>> = std::collections::HashMap::new();
+
+    let mut buf = String::new();
+    for (row, m) in meta.iter().enumerate() {
+        // Concatenate exactly what you consider searchable (title/author/genres)
+        buf.clear();
+        buf.push_str(&m.title);
+        buf.push(' ');
+        buf.push_str(&m.author);
+        buf.push(' ');
+        buf.push_str(&m.genres);
+
+        let mut norm = String::new();
+        normalize_ascii_inplace(&buf, &mut norm);
+        let bytes = norm.as_bytes();
+
+        tokenize_bytes(bytes, |tok| {
+            if tok.is_empty() { return; }
+            // pref1
+            let c0 = tok[0] as usize;
+            unsafe { pref1.get_unchecked_mut(c0) }.push(row as u32);
+
+            // pref3
+            if tok.len() >= 3 {
+                if let Some(idx) = pref3_index(tok[0], tok[1], tok[2]) {
+                    unsafe { pref3.get_unchecked_mut(idx) }.push(row as u32);
+                }
+            }
+
+            // exact short (<=6)
+            if tok.len() <= 6 {
+                let key = hash_token64(tok);
+                full6_map.entry(key).or_default().push(row as u32);
+            }
+
+            // BK-tree vocabulary (typo tolerance over the last query token).
+            // `tok` is always ASCII here (normalize_ascii_inplace only emits
+            // lowercase alnum/space), so this never panics.
+            let tok_str = std::str::from_utf8(tok).expect("ascii token");
+            if let Some(v) = vocab_map.get_mut(tok_str) {
+                v.push(row as u32);
+            } else {
+                vocab_map.insert(tok_str.to_owned().into_boxed_str(), vec![row as u32]);
+            }
+        });
+    }
+
+    // sort/dedup all postings
+    for v in pref1.iter_mut() {
+        v.sort_unstable();
+        v.dedup();
+    }
+    for v in pref3.iter_mut() {
+        v.sort_unstable();
+        v.dedup();
+    }
+    let pref1_roaring: [Postings; 256] = std::array::from_fn(|i| Postings::from_sorted_u32(&pref1[i]));
+    let pref3_roaring: Vec<Postings> = pref3.iter().map(|v| Postings::from_sorted_u32(v)).collect();
+
+    let mut full6_pairs: Vec<(u64, Vec<u32>)> = full6_map
+        .into_iter()
+        .map(|(k, mut v)| { v.sort_unstable(); v.dedup(); (k, v) })
+        .collect();
+    full6_pairs.sort_by_key(|p| p.0);
+
+    // sorted for determinism: two seals over the same docs build the same tree
+    let mut vocab: Vec<(Box<str>, Vec<u32>)> = vocab_map.into_iter().collect();
+    for (_, v) in vocab.iter_mut() {
+        v.sort_unstable();
+        v.dedup();
+    }
+    vocab.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+    let bk = BkTree::build(&vocab);
+
+    Segment {
+        s0: Lane::Owned(Arc::new(s0)),
+        s1: Lane::Owned(Arc::new(s1)),
+        s2: Lane::Owned(Arc::new(s2)),
+        s3: Lane::Owned(Arc::new(s3)),
+        pop: Arc::new(pop),
+        meta: Arc::new(meta),
+        bit_postings: Arc::new(bit_postings),
+        bit_freq: Arc::new(freq),
+
+        pref1: Arc::new(pref1_roaring),
+        pref3: Arc::new(pref3_roaring),
+        full6: Arc::new(full6_pairs),
+
+        vocab: Arc::new(vocab),
+        bk: Arc::new(bk),
+        tombstones: Segment::new_tombstones(n),
+    }
 }
 
 // ===================== builder =====================
@@ -563,115 +1653,11 @@ impl IndexBuilder {
     }
 
     pub fn seal_into_segment(&mut self) -> Segment {
-        let n = self.signatures_aos.len();
-        let mut postings: [Vec<u32>; 256] =
-            std::array::from_fn(|_| Vec::with_capacity(n / 8 + 1));
-
-        for (row, sig) in self.signatures_aos.iter().enumerate() {
-            for lane in 0..4 {
-                let mut w = sig[lane];
-                while w != 0 {
-                    let tz = w.trailing_zeros() as u16;
-                    let bit = ((lane as u16) << 6) | tz;
-                    postings[bit as usize].push(row as u32);
-                    w &= w - 1;
-                }
-            }
-        }
-
-        let mut freq = [0u32; 256];
-        for (i, v) in postings.iter_mut().enumerate() {
-            v.sort_unstable();
-            v.dedup();
-            freq[i] = v.len() as u32;
-        }
-
-        let mut s0 = Vec::with_capacity(n);
-        let mut s1 = Vec::with_capacity(n);
-        let mut s2 = Vec::with_capacity(n);
-        let mut s3 = Vec::with_capacity(n);
-        for sig in &self.signatures_aos {
-            s0.push(sig[0]);
-            s1.push(sig[1]);
-            s2.push(sig[2]);
-            s3.push(sig[3]);
-        }
-
-        // -------- build prefix + exact short-token postings from meta --------
-        let mut pref1: [Vec<u32>; 256] = std::array::from_fn(|_| Vec::new());
-        let mut pref3: Vec<Vec<u32>> = (0..PREF3_SIZE).map(|_| Vec::new()).collect();
-        let mut full6_map: std::collections::HashMap<u64, Vec<u32>> =
-            std::collections::HashMap::new();
-
-        let mut buf = String::new();
-        for (row, m) in self.meta.iter().enumerate() {
-            // Concatenate exactly what you consider searchable (title/author/genres)
-            buf.clear();
-            buf.push_str(&m.title);
-            buf.push(' ');
-            buf.push_str(&m.author);
-            buf.push(' ');
-            buf.push_str(&m.genres);
-
-            let mut norm = String::new();
-            normalize_ascii_inplace(&buf, &mut norm);
-            let bytes = norm.as_bytes();
-
-            tokenize_bytes(bytes, |tok| {
-                if tok.is_empty() { return; }
-                // pref1
-                let c0 = tok[0] as usize;
-                unsafe { pref1.get_unchecked_mut(c0) }.push(row as u32);
-
-                // pref3
-                if tok.len() >= 3 {
-                    if let Some(idx) = pref3_index(tok[0], tok[1], tok[2]) {
-                        unsafe { pref3.get_unchecked_mut(idx) }.push(row as u32);
-                    }
-                }
-
-                // exact short (<=6)
-                if tok.len() <= 6 {
-                    let key = hash_token64(tok);
-                    full6_map.entry(key).or_default().push(row as u32);
-                }
-            });
-        }
-
-        // sort/dedup all postings
-        for v in pref1.iter_mut() {
-            v.sort_unstable();
-            v.dedup();
-        }
-        for v in pref3.iter_mut() {
-            v.sort_unstable();
-            v.dedup();
-        }
-        let mut full6_pairs: Vec<(u64, Vec<u32>)> = full6_map
-            .into_iter()
-            .map(|(k, mut v)| { v.sort_unstable(); v.dedup(); (k, v) })
-            .collect();
-        full6_pairs.sort_by_key(|p| p.0);
-
-        let seg = Segment {
-            s0: Arc::new(s0),
-            s1: Arc::new(s1),
-            s2: Arc::new(s2),
-            s3: Arc::new(s3),
-            pop: Arc::new(std::mem::take(&mut self.pop)),
-            meta: Arc::new(std::mem::take(&mut self.meta)),
-            bit_postings: Arc::new(postings),
-            bit_freq: Arc::new(freq),
-
-            pref1: Arc::new(pref1),
-            pref3: Arc::new(pref3),
-            full6: Arc::new(full6_pairs),
-        };
-
-        self.signatures_aos.clear();
+        let signatures_aos = std::mem::take(&mut self.signatures_aos);
+        let pop = std::mem::take(&mut self.pop);
+        let meta = std::mem::take(&mut self.meta);
         self.id_to_row.clear();
         self.since_seal = 0;
-
-        seg
+        build_segment_from_rows(signatures_aos, pop, meta)
     }
 }
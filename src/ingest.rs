@@ -95,3 +95,117 @@ pub fn parse_ingest_pack(body: &[u8]) -> anyhow::Result<Vec<IngestItem>> {
     Ok(out)
 }
 
+// ---------- streaming packed ingest ----------
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct IngestStats {
+    pub accepted: usize,
+    pub rejected: usize,
+}
+
+// One-byte prefix on the body so the streaming parser can add fields later
+// without breaking callers still sending the plain layout.
+const PACK_VERSION_PLAIN: u8 = 0;
+const PACK_VERSION_CRC32: u8 = 1;
+
+// CRC-32 (IEEE 802.3), hand-rolled rather than pulling in a crate for one
+// polynomial-division loop; table built once at compile time.
+const CRC32_POLY: u32 = 0xEDB8_8320;
+
+const fn crc32_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut j = 0;
+        while j < 8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ CRC32_POLY } else { crc >> 1 };
+            j += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
+static CRC32_TABLE: [u32; 256] = crc32_table();
+
+pub(crate) fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for &b in bytes {
+        let idx = ((crc ^ b as u32) & 0xFF) as usize;
+        crc = (crc >> 8) ^ CRC32_TABLE[idx];
+    }
+    !crc
+}
+
+/// Send one item, blocking (with backpressure) instead of giving up on a
+/// full channel like the legacy `enqueue` timeout does — callers streaming
+/// a large pack would rather wait than silently drop records.
+#[inline]
+fn enqueue_blocking(tx: &Sender<IngestItem>, item: IngestItem) -> anyhow::Result<()> {
+    tx.send(item).map_err(|e| anyhow::anyhow!(e.to_string()))
+}
+
+/// Streaming counterpart to `parse_ingest_pack`: parses and enqueues one
+/// record at a time instead of materializing the whole body into a `Vec`
+/// first, and blocks on a full channel rather than timing out. A bad frame
+/// (failed CRC) is counted as rejected instead of failing the whole batch;
+/// a truncated/malformed tail simply stops parsing, same as the non-streaming
+/// parser.
+///
+/// Body layout: `[u8 version][records...]`, where `version` is
+/// `PACK_VERSION_PLAIN` (identical record layout to `parse_ingest_pack`) or
+/// `PACK_VERSION_CRC32` (each record additionally trailed by a
+/// `[u32 crc32]` of the concatenated `search..uri` bytes).
+pub fn ingest_pack_stream(tx: &Sender<IngestItem>, body: &[u8]) -> anyhow::Result<IngestStats> {
+    let mut stats = IngestStats::default();
+    let Some((&version, body)) = body.split_first() else {
+        return Ok(stats);
+    };
+    let with_crc = match version {
+        PACK_VERSION_PLAIN => false,
+        PACK_VERSION_CRC32 => true,
+        v => return Err(anyhow::anyhow!("unsupported ingest pack version {v}")),
+    };
+
+    let mut i = 0usize;
+    while i + 14 <= body.len() {
+        let id = u32::from_le_bytes([body[i], body[i+1], body[i+2], body[i+3]]); i += 4;
+        let sl = u16::from_le_bytes([body[i], body[i+1]]) as usize; i += 2;
+        let tl = u16::from_le_bytes([body[i], body[i+1]]) as usize; i += 2;
+        let al = u16::from_le_bytes([body[i], body[i+1]]) as usize; i += 2;
+        let gl = u16::from_le_bytes([body[i], body[i+1]]) as usize; i += 2;
+        let ul = u16::from_le_bytes([body[i], body[i+1]]) as usize; i += 2;
+        let rl = u16::from_le_bytes([body[i], body[i+1]]) as usize; i += 2;
+        let need = sl + tl + al + gl + ul + rl;
+        let trailer = if with_crc { 4 } else { 0 };
+        if i + need + trailer > body.len() { break; }
+
+        let record = &body[i..i + need];
+        i += need;
+
+        if with_crc {
+            let expected = u32::from_le_bytes([body[i], body[i+1], body[i+2], body[i+3]]);
+            i += 4;
+            if crc32(record) != expected {
+                stats.rejected += 1;
+                continue;
+            }
+        }
+
+        let search = unsafe { String::from_utf8_unchecked(record[..sl].to_vec()) };
+        let title  = unsafe { String::from_utf8_unchecked(record[sl..sl+tl].to_vec()) };
+        let author = unsafe { String::from_utf8_unchecked(record[sl+tl..sl+tl+al].to_vec()) };
+        let genres = unsafe { String::from_utf8_unchecked(record[sl+tl+al..sl+tl+al+gl].to_vec()) };
+        let url    = unsafe { String::from_utf8_unchecked(record[sl+tl+al+gl..sl+tl+al+gl+ul].to_vec()) };
+        let uri    = unsafe { String::from_utf8_unchecked(record[sl+tl+al+gl+ul..need].to_vec()) };
+
+        enqueue_blocking(tx, IngestItem {
+            id: if id == u32::MAX { None } else { Some(id) },
+            search, title, author, genres, url, uri,
+        })?;
+        stats.accepted += 1;
+    }
+    Ok(stats)
+}
+
@@ -26,6 +26,8 @@ use rustls_pki_types::{CertificateDer, PrivateKeyDer, PrivatePkcs8KeyDer};
 mod qgram;
 mod simd;
 mod accum;
+mod codec;
+mod yaz0;
 mod storage;
 mod ingest;
 mod index;
@@ -44,6 +46,74 @@ struct AppState {
     shard_id: usize,
 }
 
+// ------------- segment persistence (cold-start via mmap) -------------
+
+fn segment_path(data_dir: &str, shard_id: usize, seq: usize) -> std::path::PathBuf {
+    std::path::Path::new(data_dir).join(format!("shard{}.seg{}", shard_id, seq))
+}
+
+/// Load every `.seg` file previously persisted for this shard, in sealing
+/// order, via `Segment::open_mmap` — an O(map) cold start instead of
+/// replaying the whole WAL through `IndexBuilder` again. Returns the loaded
+/// segments, the next free `seq` to persist under, and how many WAL records
+/// (one per row, in the same order they were originally sealed) they cover,
+/// so boot can skip re-indexing that prefix.
+fn load_persisted_segments(data_dir: &str, shard_id: usize) -> (Vec<Arc<Segment>>, usize, usize) {
+    let prefix = format!("shard{}.seg", shard_id);
+    let mut found: Vec<(usize, std::path::PathBuf)> = Vec::new();
+    if let Ok(rd) = std::fs::read_dir(data_dir) {
+        for entry in rd.flatten() {
+            let name = entry.file_name();
+            if let Some(seq_str) = name.to_string_lossy().strip_prefix(&prefix) {
+                if let Ok(seq) = seq_str.parse::<usize>() {
+                    found.push((seq, entry.path()));
+                }
+            }
+        }
+    }
+    found.sort_unstable_by_key(|&(seq, _)| seq);
+
+    // `records_covered` only makes sense as a WAL-skip count if it's the sum
+    // of a *contiguous* `seq` prefix (0, 1, 2, ...) — a gap (missing file or
+    // a failed mmap) means the WAL records for that segment were never
+    // persisted, so we must stop there and let boot re-replay everything
+    // from that point on rather than silently skipping past lost rows.
+    let mut segments = Vec::with_capacity(found.len());
+    let mut next_seq = 0usize;
+    let mut records_covered = 0usize;
+    for (seq, path) in found {
+        if seq != next_seq {
+            break;
+        }
+        match Segment::open_mmap(&path) {
+            Ok(seg) => {
+                records_covered += seg.len();
+                segments.push(Arc::new(seg));
+                next_seq += 1;
+            }
+            Err(e) => {
+                eprintln!("[segment] failed to mmap {}: {}", path.display(), e);
+                break;
+            }
+        }
+    }
+    (segments, next_seq, records_covered)
+}
+
+/// Persist a freshly-sealed segment so the next boot can mmap it back
+/// instead of replaying the WAL through `IndexBuilder` from scratch.
+fn persist_segment(seg: &Segment, data_dir: &str, shard_id: usize, seq: usize) {
+    let path = segment_path(data_dir, shard_id, seq);
+    let write = || -> anyhow::Result<()> {
+        let mut f = File::create(&path)?;
+        seg.write_to(&mut f)?;
+        Ok(())
+    };
+    if let Err(e) = write() {
+        eprintln!("[segment] failed to persist {}: {}", path.display(), e);
+    }
+}
+
 #[tokio::main(flavor = "multi_thread")]
 async fn main() -> anyhow::Result<()> {
     let bind: SocketAddr = env::var("BIND").unwrap_or_else(|_| "0.0.0.0:7700".into()).parse()?;
@@ -79,13 +149,36 @@ async fn main() -> anyhow::Result<()> {
         _ => storage::SyncMode::CoalesceBytes(1 << 20),
     };
 
+    // WAL payload compression
+    let compress_mode = match env::var("WAL_COMPRESS").as_deref() {
+        Ok("yaz0") => storage::CompressMode::Yaz0,
+        _ => storage::CompressMode::Raw,
+    };
+
+    // WAL dedup policy
+    let append_policy = match env::var("WAL_DEDUP").as_deref() {
+        Ok("id") => storage::AppendPolicy::DedupById,
+        _ => storage::AppendPolicy::Always,
+    };
+
+    // Drop any torn trailing record a previous crash left in the WAL before
+    // we start replaying it.
+    storage::PackWal::recover(std::path::Path::new(&data_dir), shard_id)?;
+
     // Boot replay from atomic pack WAL
-    let mut wal = storage::PackWal::open(std::path::Path::new(&data_dir), shard_id, sync_mode)?;
+    let mut wal = storage::PackWal::open(std::path::Path::new(&data_dir), shard_id, sync_mode, compress_mode, append_policy)?;
     let replay_seg_docs: usize = env::var("REPLAY_SEG_DOCS").ok().and_then(|s| s.parse().ok()).unwrap_or(200_000);
 
+    // Cold-start from whatever segments a previous run persisted to disk
+    // (mmap'd back in, no re-indexing), then only replay the WAL tail they
+    // don't cover yet.
+    let (mut segments, mut seg_seq, records_covered) = load_persisted_segments(&data_dir, shard_id);
+
     let mut boot_builder = IndexBuilder::new();
-    let mut segments: Vec<Arc<Segment>> = Vec::new();
     if let Ok(mut rdr) = wal.reader() {
+        for _ in 0..records_covered {
+            if rdr.next()?.is_none() { break; }
+        }
         while let Some(rec) = rdr.next()? {
             let item = ingest::IngestItem {
                 id: Some(rec.id),
@@ -99,12 +192,16 @@ async fn main() -> anyhow::Result<()> {
             boot_builder.add(item);
             if boot_builder.len() >= replay_seg_docs {
                 let seg = Arc::new(boot_builder.seal_into_segment());
+                persist_segment(&seg, &data_dir, shard_id, seg_seq);
+                seg_seq += 1;
                 segments.push(seg);
             }
         }
     }
     if boot_builder.len() > 0 {
         let seg = Arc::new(boot_builder.seal_into_segment());
+        persist_segment(&seg, &data_dir, shard_id, seg_seq);
+        seg_seq += 1;
         segments.push(seg);
     }
     let view0 = Arc::new(ArcSwap::from_pointee(IndexView::from_segments(segments)));
@@ -114,7 +211,7 @@ async fn main() -> anyhow::Result<()> {
     // Builder loop
     let flush_docs: usize = env::var("FLUSH_DOCS").ok().and_then(|s| s.parse().ok()).unwrap_or(4096);
     let flush_ms: u64    = env::var("FLUSH_MS").ok().and_then(|s| s.parse().ok()).unwrap_or(5);
-    tokio::spawn(builder_loop(app.clone(), rx, wal, flush_docs, flush_ms));
+    tokio::spawn(builder_loop(app.clone(), rx, wal, flush_docs, flush_ms, data_dir.clone(), seg_seq));
 
     match mode.as_str() {
         "h1" => run_h1_plain(app.clone(), bind).await?,
@@ -254,6 +351,8 @@ async fn builder_loop(
     mut wal: storage::PackWal,
     flush_docs: usize,
     flush_ms: u64,
+    data_dir: String,
+    mut seg_seq: usize,
 ) {
     let mut last_flush = Instant::now();
     let mut builder = IndexBuilder::new();
@@ -295,11 +394,17 @@ async fn builder_loop(
         let timed_out = last_flush.elapsed() >= Duration::from_millis(flush_ms);
         if docs_since > 0 && (docs_since >= flush_docs || timed_out) {
             let seg = Arc::new(builder.seal_into_segment());
-            let mut next: Vec<Arc<Segment>> = app.view.load().segments.to_vec();
-            let total_before = next.iter().map(|s| s.len()).sum::<usize>();
-            next.push(seg.clone());
-            let total_after = total_before + seg.len();
-            app.view.store(Arc::new(IndexView::from_segments(next)));
+            persist_segment(&seg, &data_dir, app.shard_id, seg_seq);
+            seg_seq += 1;
+            // `rcu` rather than load-then-store: a concurrent publisher (e.g.
+            // `/compact`) racing this flush must never have its store clobber
+            // ours (or vice versa) and silently drop a segment from the view.
+            let published = app.view.rcu(|cur| {
+                let mut next: Vec<Arc<Segment>> = cur.segments.to_vec();
+                next.push(seg.clone());
+                IndexView::from_segments(next)
+            });
+            let total_after = published.total_docs();
             last_flush = Instant::now();
             println!("[segment] published: +{} docs (total {})", seg.len(), total_after);
         }
@@ -369,16 +474,48 @@ async fn handle(req: Request<HBody>, app: Arc<AppState>) -> anyhow::Result<Respo
             Ok(add_cors(resp))
         }
 
-        // NEW atomic packed ingest
+        // NEW atomic packed ingest: streams records off the body and blocks
+        // on a full channel instead of materializing them into a Vec first,
+        // so it runs on a blocking thread rather than the async reactor.
         (&Method::POST, "/ingest.pack") => {
             let body = req.into_body().collect().await?.to_bytes();
-            let items = ingest::parse_ingest_pack(&body)?;
-            let mut ok = 0usize;
-            for it in items {
-                if app.tx.send_async(it).await.is_ok() { ok += 1; } else { break; }
-            }
+            let tx = app.tx.clone();
+            let stats = tokio::task::spawn_blocking(move || ingest::ingest_pack_stream(&tx, &body)).await??;
             let resp = Response::builder().status(StatusCode::ACCEPTED)
-                .header("X-Ingested", ok.to_string())
+                .header("X-Ingested", stats.accepted.to_string())
+                .header("X-Rejected", stats.rejected.to_string())
+                .header(header::CONNECTION, "keep-alive")
+                .body(Full::new(Bytes::new())).unwrap();
+            Ok(add_cors(resp))
+        }
+
+        // ======== delete a doc by global id: tombstones every row it
+        // occupies across segments, live against the current view ========
+        (&Method::POST, p) if p.starts_with("/delete/") => {
+            let Ok(id) = p["/delete/".len()..].parse::<u32>() else {
+                let resp = Response::builder().status(StatusCode::BAD_REQUEST)
+                    .header(header::CONNECTION, "keep-alive")
+                    .body(Full::new(Bytes::from_static(b"bad id"))).unwrap();
+                return Ok(add_cors(resp));
+            };
+            let view = app.view.load();
+            let deleted = view.delete(id);
+            let resp = Response::builder()
+                .status(if deleted { StatusCode::NO_CONTENT } else { StatusCode::NOT_FOUND })
+                .header(header::CONNECTION, "keep-alive")
+                .body(Full::new(Bytes::new())).unwrap();
+            Ok(add_cors(resp))
+        }
+
+        // ======== compact: merge every segment's live rows into one and
+        // publish it, dropping tombstoned rows for good ========
+        (&Method::POST, "/compact") => {
+            // `rcu` instead of load-then-store: retries against whatever is
+            // actually live if a concurrent flush publishes underneath us,
+            // instead of clobbering it with a compaction built from a stale
+            // view and silently losing the just-flushed segment.
+            app.view.rcu(|cur| cur.compact());
+            let resp = Response::builder().status(StatusCode::NO_CONTENT)
                 .header(header::CONNECTION, "keep-alive")
                 .body(Full::new(Bytes::new())).unwrap();
             Ok(add_cors(resp))
@@ -3,7 +3,9 @@ use std::sync::Once;
 
 #[derive(Copy, Clone, Debug)]
 pub enum SimdPath {
+    Avx512,
     Avx2,
+    Neon,
     Scalar,
 }
 
@@ -20,11 +22,22 @@ pub fn chosen_path() -> SimdPath {
     INIT.call_once(|| unsafe {
         #[cfg(target_arch = "x86_64")]
         {
+            if std::is_x86_feature_detected!("avx512f") && std::is_x86_feature_detected!("avx512vpopcntdq") {
+                CHOSEN = SimdPath::Avx512;
+                return;
+            }
             if std::is_x86_feature_detected!("avx2") {
                 CHOSEN = SimdPath::Avx2;
                 return;
             }
         }
+        #[cfg(target_arch = "aarch64")]
+        {
+            if std::arch::is_aarch64_feature_detected!("neon") {
+                CHOSEN = SimdPath::Neon;
+                return;
+            }
+        }
         CHOSEN = SimdPath::Scalar;
     });
     unsafe { CHOSEN }
@@ -32,10 +45,17 @@ pub fn chosen_path() -> SimdPath {
 
 // ----------------- Public API -----------------
 
+// `overlap_popcnt`/`popcnt4` only touch 256 bits — too little work for
+// AVX-512's wider lanes to pay off over the AVX2 nibble-LUT, so Avx512 falls
+// back to the same AVX2 kernels here.
 #[inline(always)]
 pub fn overlap_popcnt(a: &[u64; 4], b: &[u64; 4]) -> u32 {
     match chosen_path() {
-        SimdPath::Avx2 => unsafe { overlap_popcnt_avx2(a, b) },
+        SimdPath::Avx512 | SimdPath::Avx2 => unsafe { overlap_popcnt_avx2(a, b) },
+        #[cfg(target_arch = "aarch64")]
+        SimdPath::Neon => unsafe { overlap_popcnt_neon(a, b) },
+        #[cfg(not(target_arch = "aarch64"))]
+        SimdPath::Neon => unreachable!("NEON path is never selected off aarch64"),
         SimdPath::Scalar => overlap_popcnt_scalar(a, b),
     }
 }
@@ -43,7 +63,11 @@ pub fn overlap_popcnt(a: &[u64; 4], b: &[u64; 4]) -> u32 {
 #[inline(always)]
 pub fn popcnt4(x: &[u64; 4]) -> u32 {
     match chosen_path() {
-        SimdPath::Avx2 => unsafe { popcnt4_avx2(x) },
+        SimdPath::Avx512 | SimdPath::Avx2 => unsafe { popcnt4_avx2(x) },
+        #[cfg(target_arch = "aarch64")]
+        SimdPath::Neon => unsafe { popcnt4_neon(x) },
+        #[cfg(not(target_arch = "aarch64"))]
+        SimdPath::Neon => unreachable!("NEON path is never selected off aarch64"),
         SimdPath::Scalar => popcnt4_scalar(x),
     }
 }
@@ -52,7 +76,12 @@ pub fn popcnt4(x: &[u64; 4]) -> u32 {
 #[inline(always)]
 pub fn popcnt4096_pair(a: &[u64; 64], b: &[u64; 64]) -> u32 {
     match chosen_path() {
+        SimdPath::Avx512 => unsafe { popcnt4096_avx512(a, b) },
         SimdPath::Avx2 => unsafe { popcnt4096_avx2(a, b) },
+        #[cfg(target_arch = "aarch64")]
+        SimdPath::Neon => unsafe { popcnt4096_neon(a, b) },
+        #[cfg(not(target_arch = "aarch64"))]
+        SimdPath::Neon => unreachable!("NEON path is never selected off aarch64"),
         SimdPath::Scalar => popcnt4096_scalar(a, b),
     }
 }
@@ -61,7 +90,12 @@ pub fn popcnt4096_pair(a: &[u64; 64], b: &[u64; 64]) -> u32 {
 #[inline(always)]
 pub fn popcnt4096_self(x: &[u64; 64]) -> u32 {
     match chosen_path() {
+        SimdPath::Avx512 => unsafe { popcnt4096_avx512(x, &[u64::MAX; 64]) },
         SimdPath::Avx2 => unsafe { popcnt4096_avx2(x, &[u64::MAX; 64]) },
+        #[cfg(target_arch = "aarch64")]
+        SimdPath::Neon => unsafe { popcnt4096_neon(x, &[u64::MAX; 64]) },
+        #[cfg(not(target_arch = "aarch64"))]
+        SimdPath::Neon => unreachable!("NEON path is never selected off aarch64"),
         SimdPath::Scalar => popcnt4096_scalar(x, &[u64::MAX; 64]),
     }
 }
@@ -159,3 +193,90 @@ unsafe fn popcnt256_bytesum(v: core::arch::x86_64::__m256i) -> u32 {
     _mm256_storeu_si256(tmp.as_mut_ptr() as *mut _, sums);
     (tmp[0] + tmp[1] + tmp[2] + tmp[3]) as u32
 }
+
+// ----------------- AVX-512 path -----------------
+
+/// AVX-512 4096-bit popcount using the dedicated `vpopcntdq` instruction
+/// instead of AVX2's nibble-LUT trick. 4096 bits = 64 u64s divides evenly
+/// into 8 steps of 8 lanes, so there's no scalar tail to handle.
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx512f", enable = "avx512vpopcntdq")]
+unsafe fn popcnt4096_avx512(a: &[u64; 64], b: &[u64; 64]) -> u32 {
+    use core::arch::x86_64::*;
+    let mut acc = _mm512_setzero_si512();
+    for i in (0..64).step_by(8) {
+        let va = _mm512_loadu_si512(a[i..].as_ptr() as *const __m512i);
+        let vb = _mm512_loadu_si512(b[i..].as_ptr() as *const __m512i);
+        let v = _mm512_and_si512(va, vb);
+        let cnt = _mm512_popcnt_epi64(v);
+        acc = _mm512_add_epi64(acc, cnt);
+    }
+    _mm512_reduce_add_epi64(acc) as u32
+}
+
+// ----------------- NEON path -----------------
+
+/// Fold a vector of per-byte popcounts (each 0..8) down to one scalar via
+/// the standard widening pairwise-add chain: u8x16 -> u16x8 -> u32x4 -> u64x2
+/// -> scalar.
+#[cfg(target_arch = "aarch64")]
+#[target_feature(enable = "neon")]
+unsafe fn neon_reduce_u8x16(v: core::arch::aarch64::uint8x16_t) -> u32 {
+    use core::arch::aarch64::*;
+    let v16 = vpaddlq_u8(v);
+    let v32 = vpaddlq_u16(v16);
+    let v64 = vpaddlq_u32(v32);
+    vaddvq_u64(v64) as u32
+}
+
+#[cfg(target_arch = "aarch64")]
+#[target_feature(enable = "neon")]
+unsafe fn overlap_popcnt_neon(a: &[u64; 4], b: &[u64; 4]) -> u32 {
+    use core::arch::aarch64::*;
+    let va0 = vld1q_u8(a.as_ptr() as *const u8);
+    let vb0 = vld1q_u8(b.as_ptr() as *const u8);
+    let va1 = vld1q_u8(a[2..].as_ptr() as *const u8);
+    let vb1 = vld1q_u8(b[2..].as_ptr() as *const u8);
+    let c0 = vcntq_u8(vandq_u8(va0, vb0));
+    let c1 = vcntq_u8(vandq_u8(va1, vb1));
+    neon_reduce_u8x16(vaddq_u8(c0, c1))
+}
+
+#[cfg(target_arch = "aarch64")]
+#[target_feature(enable = "neon")]
+unsafe fn popcnt4_neon(x: &[u64; 4]) -> u32 {
+    use core::arch::aarch64::*;
+    let v0 = vld1q_u8(x.as_ptr() as *const u8);
+    let v1 = vld1q_u8(x[2..].as_ptr() as *const u8);
+    let c = vaddq_u8(vcntq_u8(v0), vcntq_u8(v1));
+    neon_reduce_u8x16(c)
+}
+
+/// NEON 4096-bit popcount. Each `vcntq_u8` call produces per-byte counts in
+/// 0..8; accumulating those into a running `uint8x16_t` is safe for up to 31
+/// chunks (31*8 = 248 < 256) before a lane could overflow, so the running
+/// sum is flushed into a wider `u64` accumulator every 31 chunks.
+#[cfg(target_arch = "aarch64")]
+#[target_feature(enable = "neon")]
+unsafe fn popcnt4096_neon(a: &[u64; 64], b: &[u64; 64]) -> u32 {
+    use core::arch::aarch64::*;
+    let mut total: u64 = 0;
+    let mut acc = vdupq_n_u8(0);
+    let mut since_flush = 0u32;
+    for i in (0..64).step_by(2) {
+        let va = vld1q_u8(a[i..].as_ptr() as *const u8);
+        let vb = vld1q_u8(b[i..].as_ptr() as *const u8);
+        let v = vandq_u8(va, vb);
+        acc = vaddq_u8(acc, vcntq_u8(v));
+        since_flush += 1;
+        if since_flush == 31 {
+            total += neon_reduce_u8x16(acc) as u64;
+            acc = vdupq_n_u8(0);
+            since_flush = 0;
+        }
+    }
+    if since_flush > 0 {
+        total += neon_reduce_u8x16(acc) as u64;
+    }
+    total as u32
+}
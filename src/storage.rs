@@ -1,9 +1,13 @@
 use std::{
     fs::{File, OpenOptions},
-    io::{BufReader, Read, Write},
+    io::{BufReader, Read, Seek, Write},
     path::{Path, PathBuf},
 };
 
+use crate::codec::{read_varint, write_varint};
+use crate::ingest::crc32;
+use crate::yaz0;
+
 /// WAL sync behavior (same semantics as your original)
 #[derive(Clone, Copy, Debug)]
 pub enum SyncMode {
@@ -12,23 +16,157 @@ pub enum SyncMode {
     Never,
 }
 
-/// Atomic WAL for packed ingest:
-/// Repeated record:
-/// [u32 id][u16 sl][u16 tl][u16 al][u16 gl][u16 ul][u16 rl]
-/// [search][title][author][genres][url][uri]
+/// Per-record payload compression. Gated the same way as `SyncMode` — a
+/// shard opts in wholesale, rather than per-call.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CompressMode {
+    Raw,
+    Yaz0,
+}
+
+/// Whether `append_pack` should skip re-logging a record whose fields are
+/// byte-for-byte identical to the last thing logged under that id.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AppendPolicy {
+    Always,
+    DedupById,
+}
+
+/// FNV-1a, hand-rolled for the same reason `ingest.rs`'s CRC32 is: a few
+/// lines of hashing doesn't need a crate.
+fn fnv1a64(bytes: &[u8]) -> u64 {
+    const OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+    let mut h = OFFSET;
+    for &b in bytes {
+        h ^= b as u64;
+        h = h.wrapping_mul(PRIME);
+    }
+    h
+}
+
+fn fields_hash(search: &[u8], title: &[u8], author: &[u8], genres: &[u8], url: &[u8], uri: &[u8]) -> u64 {
+    let mut buf = Vec::with_capacity(search.len() + title.len() + author.len() + genres.len() + url.len() + uri.len());
+    buf.extend_from_slice(search);
+    buf.extend_from_slice(title);
+    buf.extend_from_slice(author);
+    buf.extend_from_slice(genres);
+    buf.extend_from_slice(url);
+    buf.extend_from_slice(uri);
+    fnv1a64(&buf)
+}
+
+/// On-disk schema for `shard{N}.pack`.
+///
+/// `Legacy` is the pre-header format: no magic, fixed `u16` field lengths and
+/// a `u32` payload length. `V1` is stamped behind a `PACK_MAGIC` + version
+/// byte at the start of freshly created files and encodes every length as an
+/// LEB128 varint instead, so a field is no longer silently truncated past
+/// 64 KiB. A shard's format is fixed at file-creation time — we never
+/// rewrite an existing file's header, so old `.pack` files keep decoding
+/// exactly as they always have.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum RecordFormat {
+    Legacy,
+    V1,
+}
+
+const PACK_MAGIC: [u8; 4] = *b"PWAL";
+const SCHEMA_V1: u8 = 1;
+
+/// Atomic WAL for packed ingest. Repeated record (see `RecordFormat` for the
+/// two on-disk header encodings):
+/// [id][flag: 0=raw, 1=yaz0][sl][tl][al][gl][ul][rl][payload_len][payload][crc32]
+/// flag 0 payload: [search][title][author][genres][url][uri]
+/// flag 1 payload: [yaz0(search..title..genres..uri)][author][url]
+///   (author/url are short structured fields, not worth compressing)
+/// The crc32 covers everything from the id through the end of the payload,
+/// so a torn trailing write — left behind by `SyncMode::Never` or
+/// `CoalesceBytes` after a crash — is detected instead of being decoded
+/// into garbage `Vec`s sized by corrupt length prefixes.
 pub struct PackWal {
     path: PathBuf,
     f: File,
+    idx_f: File,
+    write_offset: u64,
     unsynced: usize,
     sync: SyncMode,
+    compress: CompressMode,
+    format: RecordFormat,
+    policy: AppendPolicy,
+    last_hash: std::collections::HashMap<u32, u64>,
 }
 
 impl PackWal {
-    pub fn open(dir: &Path, shard_id: usize, sync: SyncMode) -> std::io::Result<Self> {
+    pub fn open(
+        dir: &Path,
+        shard_id: usize,
+        sync: SyncMode,
+        compress: CompressMode,
+        policy: AppendPolicy,
+    ) -> std::io::Result<Self> {
         std::fs::create_dir_all(dir)?;
         let path = dir.join(format!("shard{}.pack", shard_id));
-        let f = OpenOptions::new().create(true).append(true).read(true).open(&path)?;
-        Ok(Self { path, f, unsynced: 0, sync })
+        let existing_len = std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+
+        let mut f = OpenOptions::new().create(true).append(true).read(true).open(&path)?;
+        let format = if existing_len == 0 {
+            f.write_all(&PACK_MAGIC)?;
+            f.write_all(&[SCHEMA_V1])?;
+            RecordFormat::V1
+        } else {
+            detect_format(&path)?
+        };
+        let write_offset = f.metadata()?.len();
+
+        let idx_path = idx_path_for(&path);
+        if !idx_path.exists() {
+            rebuild_index_at(&path, &idx_path)?;
+        }
+        let idx_f = OpenOptions::new().create(true).append(true).read(true).open(&idx_path)?;
+
+        // Seed the dedup map from what's already on disk so a restart
+        // doesn't re-log a record that was already deduped before the crash.
+        let mut last_hash = std::collections::HashMap::new();
+        if policy == AppendPolicy::DedupById {
+            let mut reader = open_reader(&path)?;
+            while let Some(rec) = reader.next()? {
+                let h = fields_hash(&rec.search, &rec.title, &rec.author, &rec.genres, &rec.url, &rec.uri);
+                last_hash.insert(rec.id, h);
+            }
+        }
+
+        Ok(Self { path, f, idx_f, write_offset, unsynced: 0, sync, compress, format, policy, last_hash })
+    }
+
+    /// Scan `shard{shard_id}.pack` for the last valid (CRC-checked) record
+    /// and truncate any torn trailing bytes left behind by a crash, so
+    /// subsequent appends start from a consistent position. The sidecar
+    /// `.idx` is regenerated afterward, since a torn record dropped here
+    /// would otherwise leave a stale trailing entry pointing past EOF.
+    pub fn recover(dir: &Path, shard_id: usize) -> std::io::Result<()> {
+        let path = dir.join(format!("shard{}.pack", shard_id));
+        if !path.exists() {
+            return Ok(());
+        }
+        let valid_offset = {
+            let mut reader = open_reader(&path)?;
+            while reader.next()?.is_some() {}
+            reader.valid_offset
+        };
+        let f = OpenOptions::new().write(true).open(&path)?;
+        f.set_len(valid_offset)?;
+        rebuild_index_at(&path, &idx_path_for(&path))?;
+        Ok(())
+    }
+
+    /// Regenerate `shard{N}.idx` from scratch by scanning the authoritative
+    /// log, for when the sidecar index is missing or suspected stale.
+    pub fn rebuild_index(&mut self) -> std::io::Result<()> {
+        let idx_path = idx_path_for(&self.path);
+        rebuild_index_at(&self.path, &idx_path)?;
+        self.idx_f = OpenOptions::new().create(true).append(true).read(true).open(&idx_path)?;
+        Ok(())
     }
 
     #[inline]
@@ -42,17 +180,76 @@ impl PackWal {
         url: &[u8],
         uri: &[u8],
     ) -> std::io::Result<()> {
-        self.f.write_all(&id.to_le_bytes())?;
-        self.f.write_all(&(search.len() as u16).to_le_bytes())?;
-        self.f.write_all(&(title.len()  as u16).to_le_bytes())?;
-        self.f.write_all(&(author.len() as u16).to_le_bytes())?;
-        self.f.write_all(&(genres.len() as u16).to_le_bytes())?;
-        self.f.write_all(&(url.len()    as u16).to_le_bytes())?;
-        self.f.write_all(&(uri.len()    as u16).to_le_bytes())?;
-        self.f.write_all(search)?; self.f.write_all(title)?; self.f.write_all(author)?;
-        self.f.write_all(genres)?; self.f.write_all(url)?;   self.f.write_all(uri)?;
-
-        self.unsynced += 4 + 12 + search.len() + title.len() + author.len() + genres.len() + url.len() + uri.len();
+        let hash = fields_hash(search, title, author, genres, url, uri);
+        if self.policy == AppendPolicy::DedupById && self.last_hash.get(&id) == Some(&hash) {
+            return Ok(());
+        }
+
+        let payload: Vec<u8> = match self.compress {
+            CompressMode::Raw => {
+                let mut p = Vec::with_capacity(search.len() + title.len() + author.len() + genres.len() + url.len() + uri.len());
+                p.extend_from_slice(search);
+                p.extend_from_slice(title);
+                p.extend_from_slice(author);
+                p.extend_from_slice(genres);
+                p.extend_from_slice(url);
+                p.extend_from_slice(uri);
+                p
+            }
+            CompressMode::Yaz0 => {
+                let mut blob = Vec::with_capacity(search.len() + title.len() + genres.len() + uri.len());
+                blob.extend_from_slice(search);
+                blob.extend_from_slice(title);
+                blob.extend_from_slice(genres);
+                blob.extend_from_slice(uri);
+                let mut p = yaz0::compress(&blob);
+                p.extend_from_slice(author);
+                p.extend_from_slice(url);
+                p
+            }
+        };
+        let flag = match self.compress { CompressMode::Raw => 0u8, CompressMode::Yaz0 => 1u8 };
+
+        let mut record = Vec::with_capacity(4 + 1 + 12 + 4 + payload.len());
+        record.extend_from_slice(&id.to_le_bytes());
+        record.push(flag);
+        match self.format {
+            RecordFormat::Legacy => {
+                record.extend_from_slice(&(search.len() as u16).to_le_bytes());
+                record.extend_from_slice(&(title.len()  as u16).to_le_bytes());
+                record.extend_from_slice(&(author.len() as u16).to_le_bytes());
+                record.extend_from_slice(&(genres.len() as u16).to_le_bytes());
+                record.extend_from_slice(&(url.len()    as u16).to_le_bytes());
+                record.extend_from_slice(&(uri.len()    as u16).to_le_bytes());
+                record.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+            }
+            RecordFormat::V1 => {
+                write_varint(&mut record, search.len() as u64)?;
+                write_varint(&mut record, title.len() as u64)?;
+                write_varint(&mut record, author.len() as u64)?;
+                write_varint(&mut record, genres.len() as u64)?;
+                write_varint(&mut record, url.len() as u64)?;
+                write_varint(&mut record, uri.len() as u64)?;
+                write_varint(&mut record, payload.len() as u64)?;
+            }
+        }
+        record.extend_from_slice(&payload);
+
+        let crc = crc32(&record);
+        record.extend_from_slice(&crc.to_le_bytes());
+
+        let record_start = self.write_offset;
+        self.f.write_all(&record)?;
+        self.write_offset += record.len() as u64;
+
+        let mut idx_entry = [0u8; 12];
+        idx_entry[0..4].copy_from_slice(&id.to_le_bytes());
+        idx_entry[4..12].copy_from_slice(&record_start.to_le_bytes());
+        self.idx_f.write_all(&idx_entry)?;
+
+        self.last_hash.insert(id, hash);
+
+        self.unsynced += record.len();
         match self.sync {
             SyncMode::Always => { self.f.sync_data()?; self.unsynced = 0; }
             SyncMode::CoalesceBytes(thresh) => {
@@ -64,13 +261,111 @@ impl PackWal {
     }
 
     pub fn reader(&self) -> std::io::Result<PackReader> {
-        let rf = OpenOptions::new().read(true).open(&self.path)?;
-        Ok(PackReader { br: BufReader::new(rf) })
+        open_reader(&self.path)
+    }
+}
+
+fn idx_path_for(pack_path: &Path) -> PathBuf {
+    pack_path.with_extension("idx")
+}
+
+/// Full-log scan that rebuilds `idx_path` as `[u32 id][u64 byte_offset]`
+/// pairs in append order, one per valid record. The authoritative `.pack`
+/// log is always enough to reconstruct this, so a missing or stale index is
+/// never a hard failure — just a rebuild.
+fn rebuild_index_at(pack_path: &Path, idx_path: &Path) -> std::io::Result<()> {
+    let mut out = OpenOptions::new().create(true).write(true).truncate(true).open(idx_path)?;
+    let mut reader = open_reader(pack_path)?;
+    loop {
+        let start = reader.valid_offset();
+        match reader.next()? {
+            Some(rec) => {
+                let mut entry = [0u8; 12];
+                entry[0..4].copy_from_slice(&rec.id.to_le_bytes());
+                entry[4..12].copy_from_slice(&start.to_le_bytes());
+                out.write_all(&entry)?;
+            }
+            None => break,
+        }
+    }
+    out.sync_data()?;
+    Ok(())
+}
+
+/// Reads `idx_path` in full and sorts it by id, keeping the last (newest)
+/// occurrence when a record has been appended more than once under the same
+/// id. `sort_by_key` is stable, so entries sharing an id stay in their
+/// original append order within their group after sorting.
+fn load_index(idx_path: &Path) -> std::io::Result<Vec<(u32, u64)>> {
+    let mut raw = Vec::new();
+    if let Ok(mut f) = OpenOptions::new().read(true).open(idx_path) {
+        let mut buf = Vec::new();
+        f.read_to_end(&mut buf)?;
+        let mut i = 0usize;
+        while i + 12 <= buf.len() {
+            let id = u32::from_le_bytes([buf[i], buf[i+1], buf[i+2], buf[i+3]]);
+            let off = u64::from_le_bytes(buf[i+4..i+12].try_into().unwrap());
+            raw.push((id, off));
+            i += 12;
+        }
+    }
+    raw.sort_by_key(|&(id, _)| id);
+
+    let mut out: Vec<(u32, u64)> = Vec::with_capacity(raw.len());
+    for (id, off) in raw {
+        match out.last_mut() {
+            Some(last) if last.0 == id => last.1 = off,
+            _ => out.push((id, off)),
+        }
+    }
+    Ok(out)
+}
+
+/// Peek a `.pack` file's header to decide how `PackReader` should decode it.
+fn detect_format(path: &Path) -> std::io::Result<RecordFormat> {
+    let mut rf = OpenOptions::new().read(true).open(path)?;
+    let mut magic = [0u8; 4];
+    if rf.read_exact(&mut magic).is_ok() && magic == PACK_MAGIC {
+        let mut ver = [0u8; 1];
+        rf.read_exact(&mut ver)?;
+        if ver[0] != SCHEMA_V1 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("unsupported pack schema version {}", ver[0]),
+            ));
+        }
+        Ok(RecordFormat::V1)
+    } else {
+        Ok(RecordFormat::Legacy)
     }
 }
 
+fn open_reader(path: &Path) -> std::io::Result<PackReader> {
+    let mut rf = OpenOptions::new().read(true).open(path)?;
+    let mut magic = [0u8; 4];
+    let (format, valid_offset) = if rf.read_exact(&mut magic).is_ok() && magic == PACK_MAGIC {
+        let mut ver = [0u8; 1];
+        rf.read_exact(&mut ver)?;
+        if ver[0] != SCHEMA_V1 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("unsupported pack schema version {}", ver[0]),
+            ));
+        }
+        (RecordFormat::V1, (PACK_MAGIC.len() + 1) as u64)
+    } else {
+        rf.seek(std::io::SeekFrom::Start(0))?;
+        (RecordFormat::Legacy, 0)
+    };
+    Ok(PackReader { path: path.to_path_buf(), br: BufReader::new(rf), valid_offset, format, index: None })
+}
+
 pub struct PackReader {
+    path: PathBuf,
     br: BufReader<File>,
+    valid_offset: u64,
+    format: RecordFormat,
+    index: Option<Vec<(u32, u64)>>,
 }
 
 pub struct PackRec {
@@ -84,41 +379,228 @@ pub struct PackRec {
 }
 
 impl PackReader {
+    /// Byte offset in the log just past the last record that validated
+    /// cleanly. Anything beyond this (a torn trailing write) should be
+    /// truncated away — see `PackWal::recover`.
+    pub fn valid_offset(&self) -> u64 {
+        self.valid_offset
+    }
+
+    /// O(log n) random access by record id via the sidecar `.idx`, instead
+    /// of replaying the whole shard sequentially. The index is loaded and
+    /// sorted into memory on first use and cached for subsequent lookups.
+    pub fn seek_to(&mut self, id: u32) -> std::io::Result<Option<PackRec>> {
+        if self.index.is_none() {
+            self.index = Some(load_index(&idx_path_for(&self.path))?);
+        }
+        let index = self.index.as_ref().unwrap();
+        let Ok(i) = index.binary_search_by_key(&id, |&(rid, _)| rid) else {
+            return Ok(None);
+        };
+        let offset = index[i].1;
+        self.br.seek(std::io::SeekFrom::Start(offset))?;
+        self.valid_offset = offset;
+        self.next()
+    }
+
+    /// Reads the next record, verifying its trailing CRC32. A CRC mismatch
+    /// or a short read partway through a record (both signs of a torn
+    /// trailing write from a crash) are treated as clean end-of-log rather
+    /// than an error, so callers can stop replay without choking on garbage.
     pub fn next(&mut self) -> std::io::Result<Option<PackRec>> {
+        match self.format {
+            RecordFormat::Legacy => self.next_legacy(),
+            RecordFormat::V1 => self.next_v1(),
+        }
+    }
+
+    fn next_legacy(&mut self) -> std::io::Result<Option<PackRec>> {
+        let mut header = [0u8; 4 + 1 + 12 + 4];
+        if !read_exact_or_eof(&mut self.br, &mut header)? {
+            return Ok(None);
+        }
+
+        let id = u32::from_le_bytes([header[0], header[1], header[2], header[3]]);
+        let flag = header[4];
+        let sl = u16::from_le_bytes([header[5], header[6]]) as usize;
+        let tl = u16::from_le_bytes([header[7], header[8]]) as usize;
+        let al = u16::from_le_bytes([header[9], header[10]]) as usize;
+        let gl = u16::from_le_bytes([header[11], header[12]]) as usize;
+        let ul = u16::from_le_bytes([header[13], header[14]]) as usize;
+        let rl = u16::from_le_bytes([header[15], header[16]]) as usize;
+        let payload_len = u32::from_le_bytes([header[17], header[18], header[19], header[20]]) as usize;
+
+        let Some(payload) = self.read_payload_and_verify(&header, payload_len)? else {
+            return Ok(None);
+        };
+        let Some(rec) = split_payload(id, flag, sl, tl, al, gl, ul, rl, payload) else {
+            return Ok(None);
+        };
+
+        self.valid_offset += (header.len() + payload_len + 4) as u64;
+        Ok(Some(rec))
+    }
+
+    fn next_v1(&mut self) -> std::io::Result<Option<PackRec>> {
         let mut idb = [0u8; 4];
-        match self.br.read_exact(&mut idb) {
-            Ok(()) => {}
-            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
-            Err(e) => return Err(e),
+        if !read_exact_or_eof(&mut self.br, &mut idb)? {
+            return Ok(None);
         }
         let id = u32::from_le_bytes(idb);
 
-        let mut lens = [0u8; 12];
-        if let Err(e) = self.br.read_exact(&mut lens) {
-            if e.kind() == std::io::ErrorKind::UnexpectedEof { return Ok(None); }
-            return Err(e);
-        }
-        let sl = u16::from_le_bytes([lens[0], lens[1]]) as usize;
-        let tl = u16::from_le_bytes([lens[2], lens[3]]) as usize;
-        let al = u16::from_le_bytes([lens[4], lens[5]]) as usize;
-        let gl = u16::from_le_bytes([lens[6], lens[7]]) as usize;
-        let ul = u16::from_le_bytes([lens[8], lens[9]]) as usize;
-        let rl = u16::from_le_bytes([lens[10], lens[11]]) as usize;
-
-        let mut search = vec![0u8; sl];
-        let mut title  = vec![0u8; tl];
-        let mut author = vec![0u8; al];
-        let mut genres = vec![0u8; gl];
-        let mut url    = vec![0u8; ul];
-        let mut uri    = vec![0u8; rl];
-
-        if sl > 0 { self.br.read_exact(&mut search)?; }
-        if tl > 0 { self.br.read_exact(&mut title)?; }
-        if al > 0 { self.br.read_exact(&mut author)?; }
-        if gl > 0 { self.br.read_exact(&mut genres)?; }
-        if ul > 0 { self.br.read_exact(&mut url)?; }
-        if rl > 0 { self.br.read_exact(&mut uri)?; }
-
-        Ok(Some(PackRec { id, search, title, author, genres, url, uri }))
+        let mut flagb = [0u8; 1];
+        if !read_exact_or_eof(&mut self.br, &mut flagb)? {
+            return Ok(None);
+        }
+        let flag = flagb[0];
+
+        let mut header = Vec::with_capacity(5);
+        header.extend_from_slice(&idb);
+        header.push(flag);
+
+        let Some(sl) = self.read_and_track_varint(&mut header)? else { return Ok(None) };
+        let Some(tl) = self.read_and_track_varint(&mut header)? else { return Ok(None) };
+        let Some(al) = self.read_and_track_varint(&mut header)? else { return Ok(None) };
+        let Some(gl) = self.read_and_track_varint(&mut header)? else { return Ok(None) };
+        let Some(ul) = self.read_and_track_varint(&mut header)? else { return Ok(None) };
+        let Some(rl) = self.read_and_track_varint(&mut header)? else { return Ok(None) };
+        let Some(payload_len) = self.read_and_track_varint(&mut header)? else { return Ok(None) };
+
+        let Some(payload) = self.read_payload_and_verify(&header, payload_len as usize)? else {
+            return Ok(None);
+        };
+        let Some(rec) = split_payload(id, flag, sl as usize, tl as usize, al as usize, gl as usize, ul as usize, rl as usize, payload) else {
+            return Ok(None);
+        };
+
+        self.valid_offset += (header.len() + payload_len as usize + 4) as u64;
+        Ok(Some(rec))
+    }
+
+    /// Reads one varint field, appending its raw bytes to `header` so the
+    /// caller can later recompute the record's CRC over exactly the bytes
+    /// that were on disk. Returns `None` on a torn/short read.
+    fn read_and_track_varint(&mut self, header: &mut Vec<u8>) -> std::io::Result<Option<u64>> {
+        let mut tee = TeeReader { inner: &mut self.br, captured: Vec::new() };
+        match read_varint(&mut tee) {
+            Ok(v) => {
+                header.extend_from_slice(&tee.captured);
+                Ok(Some(v))
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn read_payload_and_verify(&mut self, header: &[u8], payload_len: usize) -> std::io::Result<Option<Vec<u8>>> {
+        let mut payload = vec![0u8; payload_len];
+        if !read_exact_or_eof(&mut self.br, &mut payload)? {
+            return Ok(None);
+        }
+        let mut crc_bytes = [0u8; 4];
+        if !read_exact_or_eof(&mut self.br, &mut crc_bytes)? {
+            return Ok(None);
+        }
+        let expected_crc = u32::from_le_bytes(crc_bytes);
+
+        let mut covered = Vec::with_capacity(header.len() + payload.len());
+        covered.extend_from_slice(header);
+        covered.extend_from_slice(&payload);
+        if crc32(&covered) != expected_crc {
+            return Ok(None);
+        }
+        Ok(Some(payload))
+    }
+}
+
+/// Splits a decoded record payload back into its six fields, undoing
+/// whichever `CompressMode` it was written with. Returns `None` if the
+/// payload doesn't match its declared field lengths or fails to decompress
+/// (both indicate corruption rather than a code bug).
+#[allow(clippy::too_many_arguments)]
+fn split_payload(
+    id: u32,
+    flag: u8,
+    sl: usize,
+    tl: usize,
+    al: usize,
+    gl: usize,
+    ul: usize,
+    rl: usize,
+    payload: Vec<u8>,
+) -> Option<PackRec> {
+    let (search, title, author, genres, url, uri) = match flag {
+        0 => {
+            if payload.len() != sl + tl + al + gl + ul + rl {
+                return None;
+            }
+            let mut rest = payload.as_slice();
+            let (search, r) = rest.split_at(sl); rest = r;
+            let (title, r)  = rest.split_at(tl); rest = r;
+            let (author, r) = rest.split_at(al); rest = r;
+            let (genres, r) = rest.split_at(gl); rest = r;
+            let (url, r)    = rest.split_at(ul); rest = r;
+            let uri = rest;
+            if uri.len() != rl {
+                return None;
+            }
+            (search.to_vec(), title.to_vec(), author.to_vec(), genres.to_vec(), url.to_vec(), uri.to_vec())
+        }
+        1 => {
+            if payload.len() < al + ul {
+                return None;
+            }
+            let compressed_len = payload.len() - al - ul;
+            let mut blob = Vec::with_capacity(sl + tl + gl + rl);
+            if yaz0::decompress_into(&mut &payload[..compressed_len], &mut blob).is_err()
+                || blob.len() != sl + tl + gl + rl
+            {
+                return None;
+            }
+            let search = blob[..sl].to_vec();
+            let title  = blob[sl..sl + tl].to_vec();
+            let genres = blob[sl + tl..sl + tl + gl].to_vec();
+            let uri    = blob[sl + tl + gl..sl + tl + gl + rl].to_vec();
+            let author = payload[compressed_len..compressed_len + al].to_vec();
+            let url    = payload[compressed_len + al..].to_vec();
+
+            (search, title, author, genres, url, uri)
+        }
+        _ => return None,
+    };
+
+    Some(PackRec { id, search, title, author, genres, url, uri })
+}
+
+/// Like `Read::read_exact`, but treats any EOF (even mid-buffer) as "no more
+/// valid data" rather than an error, since a torn trailing write looks
+/// exactly like a short read here.
+fn read_exact_or_eof<R: Read>(r: &mut R, buf: &mut [u8]) -> std::io::Result<bool> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match r.read(&mut buf[filled..]) {
+            Ok(0) => return Ok(false),
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(true)
+}
+
+/// Delegates reads to `inner` while also copying every byte actually
+/// consumed into `captured`, so a varint field's raw encoding can be folded
+/// into the record's CRC buffer without re-deriving it from the decoded
+/// value.
+struct TeeReader<'a, R> {
+    inner: &'a mut R,
+    captured: Vec<u8>,
+}
+
+impl<'a, R: Read> Read for TeeReader<'a, R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.captured.extend_from_slice(&buf[..n]);
+        Ok(n)
     }
 }
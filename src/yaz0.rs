@@ -0,0 +1,131 @@
+// yaz0.rs — self-contained Yaz0 LZ77 variant (magic "Yaz0" + big-endian
+// decompressed size, then 1-byte control masks driving literal/back-reference
+// groups). Used by storage.rs to transparently compress large text blobs in
+// the pack WAL.
+use std::io::{self, Read};
+
+const MAGIC: &[u8; 4] = b"Yaz0";
+const WINDOW: usize = 4096;
+const MIN_MATCH: usize = 3;
+const MAX_MATCH_EXTENDED: usize = 0x12 + 0xFF; // third-byte length can reach 273
+
+/// Compress `data` into a Yaz0 stream via greedy sliding-window matching
+/// over a 4096-byte window.
+pub fn compress(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(MAGIC.len() + 4 + data.len());
+    out.extend_from_slice(MAGIC);
+    out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+
+    let mut pos = 0usize;
+    while pos < data.len() {
+        let mut control = 0u8;
+        let mut chunk: Vec<u8> = Vec::with_capacity(3 * 8);
+
+        for bit in (0..8).rev() {
+            if pos >= data.len() {
+                break;
+            }
+
+            let window_start = pos.saturating_sub(WINDOW);
+            let max_len = (data.len() - pos).min(MAX_MATCH_EXTENDED);
+            let mut best_len = 0usize;
+            let mut best_dist = 0usize;
+            if max_len >= MIN_MATCH {
+                for cand in window_start..pos {
+                    let mut l = 0usize;
+                    while l < max_len && data[cand + l] == data[pos + l] {
+                        l += 1;
+                    }
+                    if l > best_len {
+                        best_len = l;
+                        best_dist = pos - cand;
+                        if best_len == max_len {
+                            break;
+                        }
+                    }
+                }
+            }
+
+            if best_len >= MIN_MATCH {
+                let dist_enc = best_dist - 1; // 0-based distance, fits 12 bits
+                if best_len <= 17 {
+                    let nibble = (best_len - 2) as u8; // 1..15
+                    chunk.push((nibble << 4) | ((dist_enc >> 8) as u8 & 0x0F));
+                    chunk.push((dist_enc & 0xFF) as u8);
+                } else {
+                    chunk.push((dist_enc >> 8) as u8 & 0x0F); // nibble 0 => extended
+                    chunk.push((dist_enc & 0xFF) as u8);
+                    chunk.push((best_len - 0x12) as u8);
+                }
+                pos += best_len;
+                // bit stays 0 => back-reference
+            } else {
+                control |= 1 << bit;
+                chunk.push(data[pos]);
+                pos += 1;
+            }
+        }
+
+        out.push(control);
+        out.extend_from_slice(&chunk);
+    }
+    out
+}
+
+/// Decompress a Yaz0 stream read from `r`, appending the result to `out`.
+/// Stops reading exactly once `out` has grown by the stream's declared
+/// decompressed size, so the reader's cursor lands right after the
+/// compressed bytes with no length prefix needed on the caller's side.
+pub fn decompress_into<R: Read>(r: &mut R, out: &mut Vec<u8>) -> io::Result<()> {
+    let mut magic = [0u8; 4];
+    r.read_exact(&mut magic)?;
+    if &magic != MAGIC {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "bad Yaz0 magic"));
+    }
+    let mut size_buf = [0u8; 4];
+    r.read_exact(&mut size_buf)?;
+    let target_extra = u32::from_be_bytes(size_buf) as usize;
+    let target = out.len() + target_extra;
+    out.reserve(target_extra);
+
+    while out.len() < target {
+        let mut ctrl = [0u8; 1];
+        r.read_exact(&mut ctrl)?;
+        let ctrl = ctrl[0];
+
+        for bit in (0..8).rev() {
+            if out.len() >= target {
+                break;
+            }
+            if (ctrl >> bit) & 1 != 0 {
+                let mut b = [0u8; 1];
+                r.read_exact(&mut b)?;
+                out.push(b[0]);
+            } else {
+                let mut bb = [0u8; 2];
+                r.read_exact(&mut bb)?;
+                let (b0, b1) = (bb[0], bb[1]);
+                let distance = (((b0 & 0x0F) as usize) << 8 | b1 as usize) + 1;
+                let length = if b0 >> 4 != 0 {
+                    (b0 >> 4) as usize + 2
+                } else {
+                    let mut tb = [0u8; 1];
+                    r.read_exact(&mut tb)?;
+                    tb[0] as usize + 0x12
+                };
+                let start = out
+                    .len()
+                    .checked_sub(distance)
+                    .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "Yaz0 back-reference out of range"))?;
+                for k in 0..length {
+                    if out.len() >= target {
+                        break;
+                    }
+                    let byte = out[start + k];
+                    out.push(byte);
+                }
+            }
+        }
+    }
+    Ok(())
+}